@@ -36,6 +36,10 @@ if [ ! -d /run/ezcron ]; then
     sudo mkdir /run/ezcron
 fi
 
+if [ ! -d /var/lib/ezcron ]; then
+    sudo mkdir /var/lib/ezcron
+fi
+
 cd /tmp
 sudo tar zxf ezcron.tar.gz -C /tmp
 sudo cp ezcron /usr/local/bin/
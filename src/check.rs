@@ -0,0 +1,328 @@
+use std::fs;
+use std::ops::Range;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use chrono_tz::Tz;
+use regex::Regex;
+use toml_edit::{ImDocument, Item, TableLike, Value};
+
+use crate::config::{Config, ConfigEzCron, ConfigOption, ConfigReporter, ConfigSmtp, ConfigWebhook};
+use crate::posix;
+use crate::schedule::Schedule;
+
+const REPORTER_KEYS: &[&str] = &["reports", "notifies", "on_success", "on_failure", "on_warning", "on_timeout", "on_skip"];
+
+#[derive(Debug)]
+pub struct Problem {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+// 位置を保持したまま読み込んだ設定ファイルのキーと値
+// (ドットで区切ったテーブル名のように位置が分からない場合はNoneとする)
+struct Key {
+    name: String,
+    span: Option<Range<usize>>,
+}
+
+struct Node {
+    kind: NodeKind,
+    span: Option<Range<usize>>,
+}
+
+enum NodeKind {
+    Table(Vec<(Key, Node)>),
+    Array(Vec<Node>),
+    String(String),
+    Other,
+}
+
+impl Node {
+    fn from_item(item: &Item) -> Self {
+        match item {
+            Item::Value(value) => Self::from_value(value),
+            Item::Table(table) => Self::from_table(table, table.span()),
+            Item::ArrayOfTables(tables) => Self {
+                kind: NodeKind::Array(tables.iter().map(|table| Self::from_table(table, table.span())).collect()),
+                span: tables.span(),
+            },
+            Item::None => Self { kind: NodeKind::Other, span: None },
+        }
+    }
+    fn from_value(value: &Value) -> Self {
+        let kind = match value {
+            Value::String(text) => NodeKind::String(text.value().clone()),
+            Value::Array(array) => NodeKind::Array(array.iter().map(Self::from_value).collect()),
+            Value::InlineTable(table) => return Self::from_table(table, table.span()),
+            _ => NodeKind::Other,
+        };
+        Self { kind, span: value.span() }
+    }
+    fn from_table(table: &dyn TableLike, span: Option<Range<usize>>) -> Self {
+        let entries = table.iter()
+            .map(|(name, item)| {
+                let span = table.get_key_value(name).and_then(|(key, _)| key.span());
+                (Key { name: name.to_string(), span }, Self::from_item(item))
+            })
+            .collect();
+        Self { kind: NodeKind::Table(entries), span }
+    }
+}
+
+struct Checker<'a> {
+    text: &'a str,
+    problems: Vec<Problem>,
+}
+
+impl Checker<'_> {
+    fn problem(&mut self, span: Option<Range<usize>>, message: String) {
+        // バイト位置を行、列に変換する(位置が分からない場合は先頭とする)
+        let start = span.map_or(0, |span| span.start);
+        let before = &self.text[..start.min(self.text.len())];
+        let line = before.matches('\n').count() + 1;
+        let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        self.problems.push(Problem { line, column, message });
+    }
+    fn entries<'n>(&mut self, node: &'n Node, table: &str, keys: &[&str]) -> Vec<(&'n str, &'n Node)> {
+        // テーブルのキーを確認する
+        let NodeKind::Table(entries) = &node.kind else { return Vec::new(); };
+        for (key, value) in entries {
+            if !keys.is_empty() && !keys.contains(&key.name.as_str()) {
+                let span = key.span.clone().or(value.span.clone()).or(node.span.clone());
+                self.problem(span, format!("unknown key '{}' in [{}]", key.name, table));
+            }
+        }
+        entries.iter().map(|(key, value)| (key.name.as_str(), value)).collect()
+    }
+    fn root(&mut self, root: &Node) {
+        for (key, value) in self.entries(root, "", Config::KEYS) {
+            match key {
+                "ezcron" => self.ezcron(value),
+                "option" => self.option(value, "option"),
+                "options" => {
+                    for (identifer, option) in self.entries(value, "options", &[]) {
+                        self.option(option, &format!("options.{}", identifer));
+                    }
+                },
+                _ => (),
+            }
+        }
+    }
+    fn ezcron(&mut self, ezcron: &Node) {
+        for (key, value) in self.entries(ezcron, "ezcron", ConfigEzCron::KEYS) {
+            match key {
+                "log_dir" | "pid_dir" => self.dir(value, key, true),
+                "smtp" => {
+                    self.entries(value, "ezcron.smtp", ConfigSmtp::KEYS);
+                },
+                _ => (),
+            }
+        }
+    }
+    fn option(&mut self, option: &Node, table: &str) {
+        for (key, value) in self.entries(option, table, ConfigOption::KEYS) {
+            match (key, &value.kind) {
+                ("command" | "schedule", _) if table == "option" => {
+                    self.problem(value.span.clone(), format!("{} is only available in [options.<id>]", key));
+                },
+                ("schedule", NodeKind::String(expression)) => {
+                    if let Err(err) = Schedule::parse(expression) {
+                        self.problem(value.span.clone(), format!("invalid schedule: {}", err));
+                    }
+                },
+                ("timezone", NodeKind::String(timezone)) if timezone.parse::<Tz>().is_err() => {
+                    self.problem(value.span.clone(), format!("invalid timezone '{}'", timezone));
+                },
+                ("cwd", _) => self.dir(value, key, false),
+                ("env", _) => self.env(value),
+                ("webhooks", NodeKind::Array(webhooks)) => {
+                    for webhook in webhooks {
+                        self.entries(webhook, &format!("{}.webhooks", table), ConfigWebhook::KEYS);
+                    }
+                },
+                (key, NodeKind::Array(reporters)) if REPORTER_KEYS.contains(&key) => {
+                    for reporter in reporters {
+                        self.reporter(reporter, &format!("{}.{}", table, key));
+                    }
+                },
+                _ => (),
+            }
+        }
+    }
+    fn reporter(&mut self, reporter: &Node, table: &str) {
+        match &reporter.kind {
+            NodeKind::String(command) => {
+                // 文字列はシェルで解釈されるため、先頭の語がパスの場合のみ確認する
+                // (組み込みコマンドや変数の代入、クォートなどは確認しない)
+                let program = command.split_whitespace().next().unwrap_or_default();
+                let shell_syntax = program.contains(['=', '$', '"', '\'', '`', '\\', ';', '|', '&', '(', ')', '<', '>', '*', '?', '~']);
+                if program.contains('/') && !shell_syntax {
+                    self.executable(reporter.span.clone(), program);
+                }
+            },
+            NodeKind::Array(argv) => {
+                if let Some(NodeKind::String(program)) = argv.first().map(|arg| &arg.kind) {
+                    self.executable(argv[0].span.clone(), program);
+                }
+            },
+            NodeKind::Table(_) => {
+                let entries = self.entries(reporter, table, ConfigReporter::KEYS);
+                let argv = entries.iter().find(|(key, _)| *key == "argv");
+                let command = entries.iter().find(|(key, _)| *key == "command");
+                if let Some((_, node)) = argv.or(command) {
+                    self.reporter(node, table);
+                }
+            },
+            NodeKind::Other => (),
+        }
+    }
+    fn executable(&mut self, span: Option<Range<usize>>, program: &str) {
+        // パスの指定が無ければPATHから探す
+        let candidates = if program.contains('/') {
+            vec![Path::new(program).to_path_buf()]
+        } else {
+            std::env::var("PATH").unwrap_or_default()
+                .split(':')
+                .map(|dir| Path::new(dir).join(program))
+                .collect()
+        };
+        let Some(path) = candidates.into_iter().find(|path| path.is_file()) else {
+            self.problem(span, format!("reporter '{}' is not found", program));
+            return;
+        };
+        let executable = fs::metadata(&path)
+            .is_ok_and(|metadata| metadata.permissions().mode() & 0o111 != 0);
+        if !executable {
+            self.problem(span, format!("reporter '{}' is not executable", path.display()));
+        }
+    }
+    fn dir(&mut self, value: &Node, key: &str, writable: bool) {
+        let NodeKind::String(dir) = &value.kind else { return; };
+        let path = Path::new(dir);
+        if !path.is_dir() {
+            self.problem(value.span.clone(), format!("{} '{}' does not exist", key, dir));
+        } else if writable && !posix::is_writable(path) {
+            self.problem(value.span.clone(), format!("{} '{}' is not writable", key, dir));
+        }
+    }
+    fn env(&mut self, env: &Node) {
+        let name = Regex::new(r"^[_a-zA-Z][_0-9a-zA-Z]*$").unwrap();
+        let reference = Regex::new(r"^\$([_a-zA-Z][_0-9a-zA-Z]*|\{[_a-zA-Z][_0-9a-zA-Z]*\})").unwrap();
+        let NodeKind::Table(entries) = &env.kind else { return; };
+        for (key, value) in entries {
+            if !name.is_match(&key.name) {
+                self.problem(key.span.clone(), format!("invalid environment variable name '{}'", key.name));
+            }
+            // "$NAME"、"${NAME}"以外の"$"は展開できない
+            let NodeKind::String(text) = &value.kind else { continue; };
+            if text.match_indices('$').any(|(index, _)| !reference.is_match(&text[index..])) {
+                self.problem(value.span.clone(), format!("invalid variable reference in {}: '{}'", key.name, text));
+            }
+        }
+    }
+}
+
+pub fn check(text: &str) -> Vec<Problem> {
+    let mut checker = Checker {
+        text,
+        problems: Vec::new(),
+    };
+
+    // 構文を確認する
+    let root = match ImDocument::parse(text) {
+        Ok(document) => Node::from_table(document.as_table(), None),
+        Err(err) => {
+            checker.problem(err.span(), err.message().to_string());
+            return checker.problems;
+        },
+    };
+
+    // キーとファイル、ディレクトリを確認する
+    checker.root(&root);
+
+    // 値の型などを確認する
+    if let Err(err) = toml::from_str::<Config>(text) {
+        checker.problem(err.span(), err.message().to_string());
+    }
+    checker.problems.sort_by_key(|problem| (problem.line, problem.column));
+    checker.problems
+}
+
+pub fn check_file(path: &str) -> Result<Vec<Problem>, Box<dyn std::error::Error>> {
+    let text = fs::read_to_string(path)?;
+    Ok(check(&text))
+}
+
+pub fn format(path: &str, problems: &[Problem]) -> String {
+    let mut text = String::new();
+    for problem in problems {
+        text.push_str(&format!("{}:{}:{}: {}\n", path, problem.line, problem.column, problem.message));
+    }
+    match problems.len() {
+        0 => text.push_str(&format!("{}: ok\n", path)),
+        len => text.push_str(&format!("{} problem(s) found\n", len)),
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    use crate::check;
+
+    #[test]
+    fn test_check_syntax() {
+        let problems = check::check("[ezcron]\nlog_dir=\n");
+        assert_eq!(problems.len(), 1);
+        assert_eq!((problems[0].line, problems[0].column), (2, 9));
+    }
+
+    #[test]
+    fn test_check_config() {
+        const DIR: &str = "test_check_config";
+        fs::create_dir_all(DIR).unwrap();
+        fs::write("test_check_config/report.sh", "#!/bin/sh\n").unwrap();
+        fs::set_permissions("test_check_config/report.sh", fs::Permissions::from_mode(0o644)).unwrap();
+        let problems = check::check(r#"[ezcron]
+log_dir="test_check_config"
+pid_dir="test_check_config/missing"
+stat_dir="test_check_config"
+
+[option]
+reports=["test_check_config/report.sh", ["sh", "-c", "exit 0"], {command="ls", tmplate="x"}]
+[option.env]
+PATH="/bin:${PATH"
+1A="b"
+
+[options.job01]
+timout=10
+schedule="0 25 * * *"
+timezone="Asia/Tokio"
+
+[options.job02]
+on_failure=["TEST=1 test_check_config/report.sh", "exit 1", "test_check_config/missing.sh -v", "true"]
+[[options.job02.webhooks]]
+url="http://localhost/"
+retires=3
+"#);
+        fs::remove_dir_all(DIR).unwrap();
+        let problems = problems.iter()
+            .map(|problem| (problem.line, problem.column, problem.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(problems, vec![
+            (3, 9, "pid_dir 'test_check_config/missing' does not exist"),
+            (4, 1, "unknown key 'stat_dir' in [ezcron]"),
+            (7, 10, "reporter 'test_check_config/report.sh' is not executable"),
+            (7, 80, "unknown key 'tmplate' in [option.reports]"),
+            (9, 6, "invalid variable reference in PATH: '/bin:${PATH'"),
+            (10, 1, "invalid environment variable name '1A'"),
+            (13, 1, "unknown key 'timout' in [options.job01]"),
+            (14, 10, "invalid schedule: value '25' is out of range 0-23"),
+            (15, 10, "invalid timezone 'Asia/Tokio'"),
+            (18, 61, "reporter 'test_check_config/missing.sh' is not found"),
+            (21, 1, "unknown key 'retires' in [options.job02.webhooks]"),
+        ]);
+    }
+}
@@ -1,38 +1,362 @@
 use std::collections::HashMap;
+use std::fmt;
 use std::fs;
 use registorder_map::RegistOrderMap;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
 
 #[cfg(debug_assertions)]
 const CONFIG_FILE: &str = "./etc/ezcron.toml";
 #[cfg(not(debug_assertions))]
 const CONFIG_FILE: &str = "/etc/ezcron/ezcron.toml";
+#[cfg(debug_assertions)]
+const STATE_DIR: &str = "./var/lib/ezcron";
+#[cfg(not(debug_assertions))]
+const STATE_DIR: &str = "/var/lib/ezcron";
 
 
-#[derive(Debug, Deserialize, Serialize)]
+fn default_smtp_port() -> u16 {
+    25
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ConfigSmtp {
+    pub host: String,
+    #[serde(default = "default_smtp_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub starttls: bool,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub from: String,
+    pub timeout: Option<u64>,
+}
+
+impl ConfigSmtp {
+    pub fn new(host: &str, from: &str) -> Self {
+        Self {
+            host: host.to_string(),
+            port: default_smtp_port(),
+            starttls: false,
+            username: None,
+            password: None,
+            from: from.to_string(),
+            timeout: None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct ConfigEzCron {
     pub log_dir: String,
     pub pid_dir: String,
+    pub state_dir: Option<String>,
+    pub smtp: Option<ConfigSmtp>,
+    pub spool_dir: Option<String>,
+    pub spool_max_age: Option<u64>,
+    pub spool_retry_interval: Option<u64>,
+    pub history_max_lines: Option<usize>,
+}
+
+impl ConfigEzCron {
+    pub fn state_dir(&self) -> String {
+        self.state_dir.clone().unwrap_or(STATE_DIR.to_string())
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum NotifyMode {
+    #[default]
+    Always,
+    Change,
+    FailureAndRecovery,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    Slack,
+    Teams,
+    Discord,
+    Mattermost,
+    GoogleChat,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReporterInput {
+    Json,
+    Env,
+    #[default]
+    Both,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(remote = "Self")]
+pub struct ConfigReporter {
+    #[serde(default)]
+    pub command: String,
+    pub argv: Option<Vec<String>>,
+    pub template: Option<String>,
+    pub template_file: Option<String>,
+    pub input: Option<ReporterInput>,
+    pub timeout: Option<u64>,
+}
+
+impl ConfigReporter {
+    pub fn new(command: &str) -> Self {
+        Self {
+            command: command.to_string(),
+            argv: None,
+            template: None,
+            template_file: None,
+            input: None,
+            timeout: None,
+        }
+    }
+    pub fn with_argv(argv: Vec<String>) -> Result<Self, String> {
+        // シェルを介さずに実行する
+        if argv.is_empty() {
+            return Err("argv must not be empty".to_string());
+        }
+        Ok(Self {
+            argv: Some(argv.clone()),
+            ..Self::new(&argv.join(" "))
+        })
+    }
+}
+
+impl From<&str> for ConfigReporter {
+    fn from(command: &str) -> Self {
+        Self::new(command)
+    }
+}
+
+impl From<String> for ConfigReporter {
+    fn from(command: String) -> Self {
+        Self::new(&command)
+    }
+}
+
+impl PartialEq<str> for ConfigReporter {
+    fn eq(&self, other: &str) -> bool {
+        self.command == other
+    }
+}
+
+impl PartialEq<&str> for ConfigReporter {
+    fn eq(&self, other: &&str) -> bool {
+        self.command == *other
+    }
+}
+
+impl Serialize for ConfigReporter {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ConfigReporter::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ConfigReporter {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // 文字列、テーブルのどちらでも指定できるようにする
+        struct ReporterVisitor;
+        impl<'de> Visitor<'de> for ReporterVisitor {
+            type Value = ConfigReporter;
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a command string, an argument array or a reporter table")
+            }
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Self::Value, E> {
+                Ok(ConfigReporter::new(value))
+            }
+            fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Self::Value, A::Error> {
+                let argv = Vec::<String>::deserialize(de::value::SeqAccessDeserializer::new(seq))?;
+                ConfigReporter::with_argv(argv).map_err(de::Error::custom)
+            }
+            fn visit_map<M: MapAccess<'de>>(self, map: M) -> Result<Self::Value, M::Error> {
+                let mut reporter = ConfigReporter::deserialize(de::value::MapAccessDeserializer::new(map))?;
+                match &reporter.argv {
+                    Some(argv) if argv.is_empty() => Err(de::Error::custom("argv must not be empty")),
+                    Some(argv) => {
+                        if reporter.command.is_empty() {
+                            reporter.command = argv.join(" ");
+                        }
+                        Ok(reporter)
+                    },
+                    None if reporter.command.is_empty() => Err(de::Error::missing_field("command")),
+                    None => Ok(reporter),
+                }
+            }
+        }
+        deserializer.deserialize_any(ReporterVisitor)
+    }
+}
+
+fn default_method() -> String {
+    "POST".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ConfigWebhook {
+    pub url: String,
+    #[serde(default = "default_method")]
+    pub method: String,
+    #[serde(default)]
+    pub headers: RegistOrderMap<String, String>,
+    pub timeout: Option<u64>,
+    pub retries: Option<u32>,
+    pub retry_interval: Option<u64>,
+    pub preset: Option<Preset>,
+    pub snippet_lines: Option<usize>,
+    pub template: Option<String>,
+    pub template_file: Option<String>,
+}
+
+impl ConfigWebhook {
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            method: default_method(),
+            headers: RegistOrderMap::new(),
+            timeout: None,
+            retries: None,
+            retry_interval: None,
+            preset: None,
+            snippet_lines: None,
+            template: None,
+            template_file: None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MailLog {
+    #[default]
+    None,
+    Inline,
+    Attach,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportMode {
+    #[default]
+    Serial,
+    Parallel,
+    Detached,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportLog {
+    #[default]
+    Same,
+    Sidecar,
+}
+
+// ジョブのコマンド(文字列はシェルで実行する)
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum ConfigCommand {
+    Shell(String),
+    Argv(Vec<String>),
+}
+
+impl ConfigCommand {
+    pub fn args(&self) -> Vec<String> {
+        match self {
+            ConfigCommand::Shell(command) => vec!["sh".to_string(), "-c".to_string(), command.clone()],
+            ConfigCommand::Argv(argv) => argv.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ConfigOption {
+    pub command: Option<ConfigCommand>,
+    pub schedule: Option<String>,
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub reports: Vec<ConfigReporter>,
     #[serde(default)]
-    pub reports: Vec<String>,
+    pub notifies: Vec<ConfigReporter>,
     #[serde(default)]
-    pub notifies: Vec<String>,
+    pub webhooks: Vec<ConfigWebhook>,
+    #[serde(default)]
+    pub mail_to: Vec<String>,
+    pub mail_log: Option<MailLog>,
+    pub mail_log_lines: Option<usize>,
+    #[serde(default)]
+    pub on_success: Vec<ConfigReporter>,
+    #[serde(default)]
+    pub on_failure: Vec<ConfigReporter>,
+    #[serde(default)]
+    pub on_warning: Vec<ConfigReporter>,
+    #[serde(default)]
+    pub on_timeout: Vec<ConfigReporter>,
+    #[serde(default)]
+    pub on_skip: Vec<ConfigReporter>,
     pub cwd: Option<String>,
     #[serde(default)]
     pub env: RegistOrderMap<String, String>,
+    #[serde(default)]
+    pub fail_patterns: Vec<String>,
+    #[serde(default)]
+    pub warn_patterns: Vec<String>,
+    pub success_exit_codes: Option<Vec<u32>>,
+    pub warning_exit_codes: Option<Vec<u32>>,
+    pub timeout: Option<u64>,
+    pub notify_mode: Option<NotifyMode>,
+    pub report_cooldown: Option<u64>,
+    pub max_reports_per_hour: Option<u32>,
+    pub report_timeout: Option<u64>,
+    pub fail_on_report_error: Option<bool>,
+    pub report_mode: Option<ReportMode>,
+    pub report_log: Option<ReportLog>,
+    pub output_tail_lines: Option<usize>,
+    pub output_head_lines: Option<usize>,
+}
+
+impl Default for ConfigOption {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ConfigOption {
     pub fn new() -> Self {
         Self {
-            reports: Vec::<String>::new(),
-            notifies: Vec::<String>::new(),
+            command: None,
+            schedule: None,
+            timezone: None,
+            reports: Vec::<ConfigReporter>::new(),
+            notifies: Vec::<ConfigReporter>::new(),
+            webhooks: Vec::<ConfigWebhook>::new(),
+            mail_to: Vec::<String>::new(),
+            mail_log: None,
+            mail_log_lines: None,
+            on_success: Vec::<ConfigReporter>::new(),
+            on_failure: Vec::<ConfigReporter>::new(),
+            on_warning: Vec::<ConfigReporter>::new(),
+            on_timeout: Vec::<ConfigReporter>::new(),
+            on_skip: Vec::<ConfigReporter>::new(),
             cwd: None,
             env: RegistOrderMap::new(),
+            fail_patterns: Vec::<String>::new(),
+            warn_patterns: Vec::<String>::new(),
+            success_exit_codes: None,
+            warning_exit_codes: None,
+            timeout: None,
+            notify_mode: None,
+            report_cooldown: None,
+            max_reports_per_hour: None,
+            report_timeout: None,
+            fail_on_report_error: None,
+            report_mode: None,
+            report_log: None,
+            output_tail_lines: None,
+            output_head_lines: None,
         }
     }
 }
@@ -45,23 +369,69 @@ pub struct Config {
     pub options: HashMap<String, ConfigOption>,
 }
 
-pub fn load(conf: Option<String>) -> Result<Config, Box<dyn std::error::Error>> {
-    let conf = match conf {
+impl Config {
+    // 設定ファイルのテーブルに指定できるキー(check-configで確認する)
+    pub const KEYS: &[&str] = &[
+        "ezcron", "option", "options"
+    ];
+}
+
+impl ConfigEzCron {
+    pub const KEYS: &[&str] = &[
+        "log_dir", "pid_dir", "state_dir", "smtp", "spool_dir", "spool_max_age", "spool_retry_interval",
+        "history_max_lines"
+    ];
+}
+
+impl ConfigSmtp {
+    pub const KEYS: &[&str] = &[
+        "host", "port", "starttls", "username", "password", "from", "timeout"
+    ];
+}
+
+impl ConfigReporter {
+    pub const KEYS: &[&str] = &[
+        "command", "argv", "template", "template_file", "input", "timeout"
+    ];
+}
+
+impl ConfigWebhook {
+    pub const KEYS: &[&str] = &[
+        "url", "method", "headers", "timeout", "retries", "retry_interval", "preset", "snippet_lines",
+        "template", "template_file"
+    ];
+}
+
+impl ConfigOption {
+    pub const KEYS: &[&str] = &[
+        "command", "schedule", "timezone", "reports", "notifies", "webhooks", "mail_to", "mail_log",
+        "mail_log_lines", "on_success", "on_failure", "on_warning", "on_timeout", "on_skip", "cwd", "env",
+        "fail_patterns", "warn_patterns", "success_exit_codes", "warning_exit_codes", "timeout",
+        "notify_mode", "report_cooldown", "max_reports_per_hour", "report_timeout", "fail_on_report_error",
+        "report_mode", "report_log", "output_tail_lines", "output_head_lines"
+    ];
+}
+
+pub fn path(conf: Option<String>) -> String {
+    match conf {
         Some(conf) => conf,
         None => CONFIG_FILE.to_string(),
-    };
+    }
+}
 
-    let toml_str = fs::read_to_string(conf)?;
+pub fn load(conf: Option<String>) -> Result<Config, Box<dyn std::error::Error>> {
+    let toml_str = fs::read_to_string(path(conf))?;
     let config: Config = toml::from_str(&toml_str)?;
     Ok(config)
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::unnecessary_get_then_check, clippy::unused_io_amount)]
 mod tests {
     use std::fs::File;
     use std::io::Write;
     use std::path::{Path, PathBuf};
-    use crate::config;
+    use crate::config::{self, Config, ConfigEzCron, ConfigOption, ConfigReporter, ConfigSmtp, ConfigWebhook, MailLog, NotifyMode, Preset, ReportLog, ReportMode, ReporterInput};
 
     struct TestConfigFile {
         path: Box<PathBuf>,
@@ -151,4 +521,262 @@ TEST2="VALUE2"
         assert_eq!(option.env.get(&"TEST1".to_string()), Some("VALUE1".to_string()).as_ref());
         assert_eq!(option.env.get(&"TEST2".to_string()), Some("VALUE2".to_string()).as_ref());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_config_patterns() {
+        const CONFIG_FILE: &str = "test_config_patterns.toml";
+        let _test_confg_file = TestConfigFile::new(CONFIG_FILE, r#"[ezcron]
+log_dir="var/log/ezcron"
+pid_dir="run/ezcron"
+[options.key1]
+fail_patterns=["^ERROR:", "FATAL"]
+warn_patterns=["^WARN:"]
+"#);
+        let config = config::load(Some(CONFIG_FILE.to_string())).unwrap();
+        let option = config.options.get("key1").unwrap();
+        assert_eq!(option.fail_patterns, vec!["^ERROR:", "FATAL"]);
+        assert_eq!(option.warn_patterns, vec!["^WARN:"]);
+    }
+
+    #[test]
+    fn test_config_exit_codes() {
+        const CONFIG_FILE: &str = "test_config_exit_codes.toml";
+        let _test_confg_file = TestConfigFile::new(CONFIG_FILE, r#"[ezcron]
+log_dir="var/log/ezcron"
+pid_dir="run/ezcron"
+[option]
+success_exit_codes=[0, 24]
+[options.key1]
+warning_exit_codes=[1]
+"#);
+        let config = config::load(Some(CONFIG_FILE.to_string())).unwrap();
+        let option = config.option.unwrap();
+        assert_eq!(option.success_exit_codes, Some(vec![0, 24]));
+        assert_eq!(option.warning_exit_codes, None);
+        let option = config.options.get("key1").unwrap();
+        assert_eq!(option.success_exit_codes, None);
+        assert_eq!(option.warning_exit_codes, Some(vec![1]));
+    }
+
+    #[test]
+    fn test_config_outcome_reporters() {
+        const CONFIG_FILE: &str = "test_config_outcome_reporters.toml";
+        let _test_confg_file = TestConfigFile::new(CONFIG_FILE, r#"[ezcron]
+log_dir="var/log/ezcron"
+pid_dir="run/ezcron"
+[options.key1]
+on_success=["success.sh"]
+on_failure=["failure.sh"]
+on_warning=["warning.sh"]
+on_timeout=["timeout.sh"]
+on_skip=["skip.sh"]
+timeout=3600
+"#);
+        let config = config::load(Some(CONFIG_FILE.to_string())).unwrap();
+        let option = config.options.get("key1").unwrap();
+        assert_eq!(option.on_success, vec!["success.sh"]);
+        assert_eq!(option.on_failure, vec!["failure.sh"]);
+        assert_eq!(option.on_warning, vec!["warning.sh"]);
+        assert_eq!(option.on_timeout, vec!["timeout.sh"]);
+        assert_eq!(option.on_skip, vec!["skip.sh"]);
+        assert_eq!(option.timeout, Some(3600));
+        assert_eq!(option.notify_mode, None);
+    }
+
+    #[test]
+    fn test_config_notify_mode() {
+        const CONFIG_FILE: &str = "test_config_notify_mode.toml";
+        let _test_confg_file = TestConfigFile::new(CONFIG_FILE, r#"[ezcron]
+log_dir="var/log/ezcron"
+pid_dir="run/ezcron"
+state_dir="var/lib/ezcron"
+[option]
+notify_mode="change"
+report_cooldown=3600
+max_reports_per_hour=4
+[options.key1]
+notify_mode="failure-and-recovery"
+"#);
+        let config = config::load(Some(CONFIG_FILE.to_string())).unwrap();
+        assert_eq!(config.ezcron.state_dir(), "var/lib/ezcron".to_string());
+        let option = config.option.unwrap();
+        assert_eq!(option.notify_mode, Some(NotifyMode::Change));
+        assert_eq!(option.report_cooldown, Some(3600));
+        assert_eq!(option.max_reports_per_hour, Some(4));
+        let option = config.options.get("key1").unwrap();
+        assert_eq!(option.notify_mode, Some(NotifyMode::FailureAndRecovery));
+    }
+
+    #[test]
+    fn test_config_webhooks() {
+        const CONFIG_FILE: &str = "test_config_webhooks.toml";
+        let _test_confg_file = TestConfigFile::new(CONFIG_FILE, r#"[ezcron]
+log_dir="var/log/ezcron"
+pid_dir="run/ezcron"
+[[options.key1.webhooks]]
+url="https://example.com/hook"
+[[options.key1.webhooks]]
+url="https://chat.googleapis.com/v1/spaces/XXX/messages"
+preset="googlechat"
+snippet_lines=10
+[[options.key1.webhooks]]
+url="https://example.com/hook2"
+method="PUT"
+timeout=5
+retries=3
+[options.key1.webhooks.headers]
+Authorization="Bearer ${TOKEN}"
+"#);
+        let config = config::load(Some(CONFIG_FILE.to_string())).unwrap();
+        let option = config.options.get("key1").unwrap();
+        assert_eq!(option.webhooks.len(), 3);
+        assert_eq!(option.webhooks[0].url, "https://example.com/hook");
+        assert_eq!(option.webhooks[0].method, "POST");
+        assert_eq!(option.webhooks[0].timeout, None);
+        assert_eq!(option.webhooks[0].preset, None);
+        assert_eq!(option.webhooks[1].preset, Some(Preset::GoogleChat));
+        assert_eq!(option.webhooks[1].snippet_lines, Some(10));
+        assert_eq!(option.webhooks[2].method, "PUT");
+        assert_eq!(option.webhooks[2].timeout, Some(5));
+        assert_eq!(option.webhooks[2].retries, Some(3));
+        assert_eq!(option.webhooks[2].headers.get(&"Authorization".to_string()), Some("Bearer ${TOKEN}".to_string()).as_ref());
+    }
+
+    #[test]
+    fn test_config_smtp() {
+        const CONFIG_FILE: &str = "test_config_smtp.toml";
+        let _test_confg_file = TestConfigFile::new(CONFIG_FILE, r#"[ezcron]
+log_dir="var/log/ezcron"
+pid_dir="run/ezcron"
+[ezcron.smtp]
+host="smtp.example.com"
+port=587
+starttls=true
+username="user"
+password="${SMTP_PASSWORD}"
+from="ezcron@example.com"
+[options.key1]
+mail_to=["admin@example.com"]
+mail_log="attach"
+mail_log_lines=100
+"#);
+        let config = config::load(Some(CONFIG_FILE.to_string())).unwrap();
+        let smtp = config.ezcron.smtp.unwrap();
+        assert_eq!(smtp.host, "smtp.example.com");
+        assert_eq!(smtp.port, 587);
+        assert!(smtp.starttls);
+        assert_eq!(smtp.username, Some("user".to_string()));
+        assert_eq!(smtp.password, Some("${SMTP_PASSWORD}".to_string()));
+        assert_eq!(smtp.from, "ezcron@example.com");
+        let option = config.options.get("key1").unwrap();
+        assert_eq!(option.mail_to, vec!["admin@example.com"]);
+        assert_eq!(option.mail_log, Some(MailLog::Attach));
+        assert_eq!(option.mail_log_lines, Some(100));
+    }
+
+    #[test]
+    fn test_config_reporter_table() {
+        const CONFIG_FILE: &str = "test_config_reporter_table.toml";
+        let _test_confg_file = TestConfigFile::new(CONFIG_FILE, r#"[ezcron]
+log_dir="var/log/ezcron"
+pid_dir="run/ezcron"
+[option]
+reports=["report.sh", {command="report2.sh", template="{{ identifer }}"}, ["/usr/bin/logger", "-t", "ezcron", "{outcome}"], {argv=["report 3.sh"], input="env"}]
+[[option.on_failure]]
+command="failure.sh"
+template_file="/path/to/failure.tmpl"
+input="env"
+"#);
+        let config = config::load(Some(CONFIG_FILE.to_string())).unwrap();
+        let option = config.option.unwrap();
+        assert_eq!(option.reports, vec!["report.sh", "report2.sh", "/usr/bin/logger -t ezcron {outcome}", "report 3.sh"]);
+        assert_eq!(option.reports[0].argv, None);
+        assert_eq!(option.reports[2].argv, Some(vec!["/usr/bin/logger".to_string(), "-t".to_string(), "ezcron".to_string(), "{outcome}".to_string()]));
+        assert_eq!(option.reports[3].argv, Some(vec!["report 3.sh".to_string()]));
+        assert_eq!(option.reports[3].input, Some(ReporterInput::Env));
+        assert_eq!(option.reports[0].template, None);
+        assert_eq!(option.reports[1].template, Some("{{ identifer }}".to_string()));
+        assert_eq!(option.on_failure[0].command, "failure.sh");
+        assert_eq!(option.on_failure[0].template_file, Some("/path/to/failure.tmpl".to_string()));
+        assert_eq!(option.on_failure[0].input, Some(ReporterInput::Env));
+        assert_eq!(option.reports[0].input, None);
+    }
+
+    #[test]
+    fn test_config_report_timeout() {
+        const CONFIG_FILE: &str = "test_config_report_timeout.toml";
+        let _test_confg_file = TestConfigFile::new(CONFIG_FILE, r#"[ezcron]
+log_dir="var/log/ezcron"
+pid_dir="run/ezcron"
+[option]
+reports=["report.sh", {command="report2.sh", timeout=10}]
+report_timeout=60
+fail_on_report_error=true
+report_mode="parallel"
+[options.key1]
+report_timeout=120
+report_mode="detached"
+report_log="sidecar"
+"#);
+        let config = config::load(Some(CONFIG_FILE.to_string())).unwrap();
+        let option = config.option.unwrap();
+        assert_eq!(option.reports[0].timeout, None);
+        assert_eq!(option.reports[1].timeout, Some(10));
+        assert_eq!(option.report_timeout, Some(60));
+        assert_eq!(option.fail_on_report_error, Some(true));
+        assert_eq!(option.report_mode, Some(ReportMode::Parallel));
+        assert_eq!(option.report_log, None);
+        let option = config.options.get("key1").unwrap();
+        assert_eq!(option.report_timeout, Some(120));
+        assert_eq!(option.fail_on_report_error, None);
+        assert_eq!(option.report_mode, Some(ReportMode::Detached));
+        assert_eq!(option.report_log, Some(ReportLog::Sidecar));
+    }
+
+    #[test]
+    fn test_config_keys() {
+        // 指定できるキーの一覧が構造体のフィールドと一致するか確認する
+        fn keys<T: serde::Serialize>(value: T) -> Vec<String> {
+            let serde_json::Value::Object(map) = serde_json::to_value(value).unwrap() else { panic!("not a table") };
+            let mut keys = map.keys().cloned().collect::<Vec<String>>();
+            keys.sort();
+            keys
+        }
+        fn sorted(keys: &[&str]) -> Vec<String> {
+            let mut keys = keys.iter().map(|key| key.to_string()).collect::<Vec<String>>();
+            keys.sort();
+            keys
+        }
+        let config = Config {
+            ezcron: ConfigEzCron::default(),
+            option: None,
+            options: Default::default(),
+        };
+        assert_eq!(keys(config), sorted(Config::KEYS));
+        assert_eq!(keys(ConfigEzCron::default()), sorted(ConfigEzCron::KEYS));
+        assert_eq!(keys(ConfigSmtp::new("localhost", "ezcron@localhost")), sorted(ConfigSmtp::KEYS));
+        assert_eq!(keys(ConfigReporter::new("report.sh")), sorted(ConfigReporter::KEYS));
+        assert_eq!(keys(ConfigWebhook::new("http://localhost/")), sorted(ConfigWebhook::KEYS));
+        assert_eq!(keys(ConfigOption::new()), sorted(ConfigOption::KEYS));
+    }
+
+    #[test]
+    fn test_config_command() {
+        const CONFIG_FILE: &str = "test_config_command.toml";
+        let _test_confg_file = TestConfigFile::new(CONFIG_FILE, r#"[ezcron]
+log_dir="var/log/ezcron"
+pid_dir="run/ezcron"
+[options.job01]
+command="ls -al | wc -l"
+[options.job02]
+command=["ls", "-al"]
+[options.job03]
+cwd="/tmp"
+"#);
+        let config = config::load(Some(CONFIG_FILE.to_string())).unwrap();
+        let command = |id: &str| config.options.get(id).unwrap().command.as_ref().map(|command| command.args());
+        assert_eq!(command("job01"), Some(vec!["sh".to_string(), "-c".to_string(), "ls -al | wc -l".to_string()]));
+        assert_eq!(command("job02"), Some(vec!["ls".to_string(), "-al".to_string()]));
+        assert_eq!(command("job03"), None);
+    }
+}
@@ -0,0 +1,84 @@
+use std::thread;
+use std::time::{Duration, Instant};
+use chrono::Local;
+
+use crate::logger::Logger;
+use crate::pid;
+use crate::posix;
+use crate::report::ReportAction;
+use crate::status::{self, JobStatus};
+
+const KILL_INTERVAL: u64 = 100;
+pub const DEFAULT_GRACE: u64 = 10;
+
+pub fn find(target: &str, pid_dir: &str) -> Result<JobStatus, Box<dyn std::error::Error>> {
+    // 識別子またはUUIDが一致する実行中のジョブを探す
+    let mut statuses = status::list(pid_dir, Local::now())?
+        .into_iter()
+        .filter(|status| status.alive)
+        .filter(|status| status.identifer == target || status.uuid.as_deref() == Some(target))
+        .collect::<Vec<JobStatus>>();
+    match statuses.len() {
+        0 => Err(format!("running job '{}' is not found", target).into()),
+        1 => Ok(statuses.remove(0)),
+        _ => Err(format!("'{}' has {} running jobs, specify the UUID", target, statuses.len()).into()),
+    }
+}
+
+fn signal(status: &JobStatus, sig: libc::c_int) -> std::io::Result<()> {
+    // プロセスグループに送り、グループで無い場合はプロセスに送る
+    posix::kill_group(status.pid, sig)
+        .or_else(|_| posix::kill(status.pid, sig))
+}
+
+fn is_alive(status: &JobStatus) -> bool {
+    // 子孫のプロセスが残っている場合も終了していないとする
+    status::is_group_alive(status.pid) || status::is_alive(status.pid)
+}
+
+fn record(status: &JobStatus, pid_dir: &str, action: &str) -> Result<(), Box<dyn std::error::Error>> {
+    // 実行中のログとレポートに操作を記録する
+    if let Some(log) = &status.log {
+        let mut logger = Logger::open(log)?;
+        logger.write(&format!("{} by ezcron (pid {})", action, std::process::id()))?;
+    }
+    if let Some(uuid) = &status.uuid {
+        let path = pid::actions_path(pid_dir, &status.identifer, uuid);
+        pid::append_action(&path, &ReportAction {
+            action: action.to_string(),
+            at: Local::now(),
+        })?;
+    }
+    Ok(())
+}
+
+pub fn kill(status: &JobStatus, pid_dir: &str, grace: u64) -> Result<bool, Box<dyn std::error::Error>> {
+    // SIGTERMを送り、猶予時間を過ぎても終了しない場合はSIGKILLを送る
+    record(status, pid_dir, "kill(SIGTERM)")?;
+    signal(status, libc::SIGTERM)?;
+    let start = Instant::now();
+    while start.elapsed() < Duration::from_secs(grace) {
+        if !is_alive(status) {
+            return Ok(false);
+        }
+        thread::sleep(Duration::from_millis(KILL_INTERVAL));
+    }
+    if !is_alive(status) {
+        return Ok(false);
+    }
+    record(status, pid_dir, "kill(SIGKILL)")?;
+    signal(status, libc::SIGKILL)?;
+    Ok(true)
+}
+
+pub fn pause(status: &JobStatus, pid_dir: &str) -> Result<(), Box<dyn std::error::Error>> {
+    record(status, pid_dir, "pause(SIGSTOP)")?;
+    signal(status, libc::SIGSTOP)?;
+    Ok(())
+}
+
+pub fn resume(status: &JobStatus, pid_dir: &str) -> Result<(), Box<dyn std::error::Error>> {
+    signal(status, libc::SIGCONT)?;
+    record(status, pid_dir, "resume(SIGCONT)")?;
+    Ok(())
+}
@@ -0,0 +1,219 @@
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+use chrono::{DateTime, Local};
+use chrono_tz::Tz;
+use subprocess::{Exec, NullFile, Popen};
+
+use crate::config::{self, Config};
+use crate::logger::Logger;
+use crate::posix;
+use crate::schedule::Schedule;
+
+// 実行日時とシグナルを確認する間隔(ミリ秒)
+const POLL_INTERVAL: u64 = 1000;
+
+pub struct Job {
+    pub identifer: String,
+    pub expression: String,
+    pub timezone: Option<Tz>,
+    pub next: Option<DateTime<Local>>,
+    schedule: Schedule,
+}
+
+impl Job {
+    pub fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        // タイムゾーンの指定があればその時刻で計算する
+        match self.timezone {
+            Some(timezone) => self.schedule.next_after(&after.with_timezone(&timezone))
+                .map(|next| next.with_timezone(&Local)),
+            None => self.schedule.next_after(&after),
+        }
+    }
+}
+
+pub fn jobs(conf: &Config, now: DateTime<Local>) -> (Vec<Job>, Vec<String>) {
+    // [options.識別子]のscheduleからジョブを得る([option]のtimezoneはデフォルトとする)
+    let default_timezone = conf.option.as_ref().and_then(|option| option.timezone.clone());
+    let mut identifers = conf.options.keys().collect::<Vec<&String>>();
+    identifers.sort();
+    let mut jobs = Vec::new();
+    let mut errors = Vec::new();
+    for identifer in identifers {
+        let option = &conf.options[identifer];
+        let Some(expression) = &option.schedule else { continue; };
+        let job = (|| -> Result<Job, String> {
+            if option.command.is_none() {
+                return Err("command is not specified".to_string());
+            }
+            let schedule = Schedule::parse(expression)?;
+            let timezone = option.timezone.clone().or(default_timezone.clone())
+                .map(|timezone| timezone.parse::<Tz>().map_err(|_| format!("invalid timezone '{}'", timezone)))
+                .transpose()?;
+            let mut job = Job {
+                identifer: identifer.clone(),
+                expression: expression.clone(),
+                timezone,
+                next: None,
+                schedule,
+            };
+            job.next = job.next_after(now);
+            Ok(job)
+        })();
+        match job {
+            Ok(job) => jobs.push(job),
+            Err(err) => errors.push(format!("{}: {}", identifer, err)),
+        }
+    }
+    (jobs, errors)
+}
+
+fn log(logger: &mut Logger, line: &str) -> std::io::Result<()> {
+    // フォアグラウンドで動かす場合のために標準出力にも出力する
+    println!("{}", line);
+    logger.write(line)
+}
+
+fn format_next(job: &Job) -> String {
+    job.next.map_or("never".to_string(), |next| next.to_rfc3339())
+}
+
+fn schedule(conf: &Config, logger: &mut Logger) -> std::io::Result<Vec<Job>> {
+    // 実行するスケジュールをログに出力する
+    let (jobs, errors) = jobs(conf, Local::now());
+    for error in errors {
+        log(logger, &format!("schedule error! {}", error))?;
+    }
+    for job in jobs.iter() {
+        let timezone = job.timezone.map_or("local".to_string(), |timezone| timezone.to_string());
+        log(logger, &format!("schedule {} '{}' ({}) next {}", job.identifer, job.expression, timezone, format_next(job)))?;
+    }
+    if jobs.is_empty() {
+        log(logger, "no scheduled jobs")?;
+    }
+    Ok(jobs)
+}
+
+fn launch(exe: &Path, config_path: &str, identifer: &str) -> subprocess::Result<Popen> {
+    // 通常の実行と同じく"ezcron run"で実行する
+    // (デーモンの終了時にジョブの終了を待たないよう、終了はpollで確認する)
+    Exec::cmd(exe)
+        .args(&["run", "-c", config_path, identifer])
+        .stdin(NullFile)
+        .stdout(NullFile)
+        .stderr(NullFile)
+        .detached()
+        .popen()
+}
+
+pub fn run(config_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let conf = config::load(Some(config_path.to_string()))?;
+    let mut logger = Logger::new("daemon", &conf.ezcron.log_dir)?;
+    for sig in [libc::SIGHUP, libc::SIGTERM, libc::SIGINT] {
+        posix::catch_signal(sig)?;
+    }
+    let exe = std::env::current_exe()?;
+    log(&mut logger, &format!("daemon started (pid {}) config '{}'", std::process::id(), config_path))?;
+    let mut jobs = schedule(&conf, &mut logger)?;
+    let mut running: Vec<(String, u32, Popen)> = Vec::new();
+
+    loop {
+        // 終了のシグナルを受け取れば終了する(実行中のジョブはそのまま続ける)
+        if posix::take_signal(libc::SIGTERM) || posix::take_signal(libc::SIGINT) {
+            log(&mut logger, &format!("daemon stopped ({} running)", running.len()))?;
+            return Ok(());
+        }
+
+        // SIGHUPを受け取れば設定ファイルを読み込み直す
+        if posix::take_signal(libc::SIGHUP) {
+            log(&mut logger, "reloading configuration")?;
+            match config::load(Some(config_path.to_string())) {
+                Ok(conf) => jobs = schedule(&conf, &mut logger)?,
+                Err(err) => log(&mut logger, &format!("reload error! '{}', keeping the current schedule", err))?,
+            }
+        }
+
+        // 終了したジョブを回収する
+        let mut finished = Vec::new();
+        running.retain_mut(|(identifer, pid, popen)| match popen.poll() {
+            Some(status) => {
+                finished.push(format!("finished {} (pid {}) {:?}", identifer, pid, status));
+                false
+            },
+            None => true,
+        });
+        for line in finished {
+            log(&mut logger, &line)?;
+        }
+
+        // 実行日時になったジョブを起動する
+        let now = Local::now();
+        for job in jobs.iter_mut() {
+            if job.next.is_none_or(|next| next > now) {
+                continue;
+            }
+            match launch(&exe, config_path, &job.identifer) {
+                Ok(popen) => {
+                    let pid = popen.pid().unwrap_or(0);
+                    log(&mut logger, &format!("started {} (pid {})", job.identifer, pid))?;
+                    running.push((job.identifer.clone(), pid, popen));
+                },
+                Err(err) => log(&mut logger, &format!("starting error! {} '{}'", job.identifer, err))?,
+            }
+            job.next = job.next_after(now);
+            log(&mut logger, &format!("next {} {}", job.identifer, format_next(job)))?;
+        }
+
+        thread::sleep(Duration::from_millis(POLL_INTERVAL));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use chrono::{DateTime, Local, SecondsFormat, Utc};
+    use crate::config::{Config, ConfigCommand, ConfigEzCron, ConfigOption};
+    use crate::daemon;
+
+    #[test]
+    fn test_daemon_jobs() {
+        let job = |schedule: &str, timezone: Option<&str>| ConfigOption {
+            command: Some(ConfigCommand::Shell("true".to_string())),
+            schedule: Some(schedule.to_string()),
+            timezone: timezone.map(|timezone| timezone.to_string()),
+            ..ConfigOption::new()
+        };
+        let mut options = HashMap::new();
+        options.insert("job01".to_string(), job("0 9 * * *", None));
+        options.insert("job02".to_string(), job("0 9 * * *", Some("America/New_York")));
+        options.insert("job03".to_string(), job("0 9 * *", None));
+        options.insert("job04".to_string(), job("0 9 * * *", Some("Mars/Olympus")));
+        options.insert("job05".to_string(), ConfigOption {
+            command: None,
+            ..job("0 9 * * *", None)
+        });
+        options.insert("job06".to_string(), ConfigOption::new());
+        let conf = Config {
+            ezcron: ConfigEzCron::default(),
+            option: Some(ConfigOption {
+                timezone: Some("Asia/Tokyo".to_string()),
+                ..ConfigOption::new()
+            }),
+            options,
+        };
+        let now = DateTime::parse_from_rfc3339("2023-12-11T10:00:00+09:00").unwrap().with_timezone(&Local);
+        let (jobs, errors) = daemon::jobs(&conf, now);
+        let next = jobs.iter()
+            .map(|job| (job.identifer.as_str(), job.next.unwrap().with_timezone(&Utc).to_rfc3339_opts(SecondsFormat::Secs, true)))
+            .collect::<Vec<_>>();
+        assert_eq!(next, vec![
+            ("job01", "2023-12-12T00:00:00Z".to_string()),
+            ("job02", "2023-12-11T14:00:00Z".to_string()),
+        ]);
+        assert_eq!(errors, vec![
+            "job03: '0 9 * *' must have 5 fields",
+            "job04: invalid timezone 'Mars/Olympus'",
+            "job05: command is not specified",
+        ]);
+    }
+}
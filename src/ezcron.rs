@@ -1,24 +1,120 @@
+use std::collections::VecDeque;
 use std::ffi::OsString;
-use std::io::{BufRead, BufReader};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Seek, SeekFrom, Write};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::{Duration, Instant};
 use chrono::Local;
 use getopts::Matches;
-use subprocess::{Exec, ExitStatus, Popen, PopenConfig, Redirection};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use subprocess::{ExitStatus, Popen, PopenConfig, Redirection};
+use uuid::Uuid;
 
-use crate::config::{self, ConfigOption};
-use crate::logger::Logger;
-use crate::pid;
+use crate::config::{self, ConfigOption, ConfigReporter, ConfigSmtp, ReporterInput, ConfigWebhook, MailLog, NotifyMode, ReportLog, ReportMode};
+use crate::history::{self, RunRecord};
+use crate::logger::{self, Logger};
+use crate::mail;
+use crate::pid::{self, RunInfo};
 use crate::posix;
-use crate::report::{Report, ReportStatus};
+use crate::preset;
+use crate::report::{Report, ReportMatch, ReportOutcome, ReporterResult, ReportStatus};
+use crate::spool::{self, SpoolEntry};
+use crate::state::{self, State};
+use crate::status;
+use crate::template;
+use crate::webhook;
+
+// 通知に失敗した場合の終了コード
+pub const EXIT_REPORT_ERROR: i32 = 3;
+const DEFAULT_REPORT_TIMEOUT: u64 = 600;
+const DEFAULT_OUTPUT_TAIL_LINES: usize = 20;
+// 制限時間を過ぎてSIGTERMを送ってからSIGKILLを送るまでの猶予時間(秒)
+const TIMEOUT_GRACE: u64 = 10;
+// シグナルと制限時間を確認する間隔(ミリ秒)
+const WATCH_INTERVAL: u64 = 100;
+
+// 通知先
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Delivery {
+    Script {
+        reporter: ConfigReporter,
+        timeout: u64,
+    },
+    Webhook {
+        webhook: ConfigWebhook,
+    },
+    Mail {
+        to: Vec<String>,
+        mail_log: MailLog,
+        mail_log_lines: usize,
+    },
+}
+
+// 設定した環境変数と、その設定元
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvValue {
+    pub name: String,
+    pub value: String,
+    pub previous: Option<String>,
+    pub source: String,
+}
 
 #[derive(Debug, Default)]
 pub struct EzCron {
     log_dir: String,
     pid_dir: String,
+    state_dir: String,
     identifer: String,
-    reports: Vec<String>,
-    notifies: Vec<String>,
+    smtp: Option<ConfigSmtp>,
+    reports: Vec<ConfigReporter>,
+    notifies: Vec<ConfigReporter>,
+    webhooks: Vec<ConfigWebhook>,
+    mail_to: Vec<String>,
+    mail_log: MailLog,
+    mail_log_lines: usize,
+    on_success: Vec<ConfigReporter>,
+    on_failure: Vec<ConfigReporter>,
+    on_warning: Vec<ConfigReporter>,
+    on_timeout: Vec<ConfigReporter>,
+    on_skip: Vec<ConfigReporter>,
     cwd: Option<String>,
     multipled: bool,
+    timeout: Option<u64>,
+    timeout_grace: u64,
+    notify_mode: NotifyMode,
+    report_cooldown: Option<u64>,
+    max_reports_per_hour: Option<u32>,
+    report_timeout: u64,
+    fail_on_report_error: bool,
+    report_mode: ReportMode,
+    report_log: ReportLog,
+    spool_dir: Option<String>,
+    spool_retry_interval: u64,
+    history_max_lines: usize,
+    output_tail_lines: usize,
+    output_head_lines: usize,
+    fail_patterns: Vec<Regex>,
+    warn_patterns: Vec<Regex>,
+    success_exit_codes: Vec<u32>,
+    warning_exit_codes: Vec<u32>,
+    env: Vec<EnvValue>,
+    command: Vec<String>,
+}
+
+fn set_env(env: &mut Vec<EnvValue>, name: &str, value: &str, source: &str) {
+    // 展開前の値を記録して環境変数をセットする
+    let previous = std::env::var(name).ok();
+    crate::env::set_var(name, value);
+    env.push(EnvValue {
+        name: name.to_string(),
+        value: std::env::var(name).unwrap_or_default(),
+        previous,
+        source: source.to_string(),
+    });
 }
 
 impl EzCron {
@@ -33,33 +129,117 @@ impl EzCron {
         let option = conf.option.unwrap_or(ConfigOption::new());
         let mut reports = option.reports;
         let mut notifies = option.notifies;
+        let mut webhooks = option.webhooks;
+        let mut mail_to = option.mail_to;
+        let mut mail_log = option.mail_log;
+        let mut mail_log_lines = option.mail_log_lines;
+        let mut on_success = option.on_success;
+        let mut on_failure = option.on_failure;
+        let mut on_warning = option.on_warning;
+        let mut on_timeout = option.on_timeout;
+        let mut on_skip = option.on_skip;
         let mut cwd = option.cwd;
+        let mut fail_patterns = option.fail_patterns;
+        let mut warn_patterns = option.warn_patterns;
+        let mut success_exit_codes = option.success_exit_codes;
+        let mut warning_exit_codes = option.warning_exit_codes;
+        let mut timeout = option.timeout;
+        let mut notify_mode = option.notify_mode;
+        let mut report_cooldown = option.report_cooldown;
+        let mut max_reports_per_hour = option.max_reports_per_hour;
+        let mut report_timeout = option.report_timeout;
+        let mut fail_on_report_error = option.fail_on_report_error;
+        let mut report_mode = option.report_mode;
+        let mut report_log = option.report_log;
+        let mut output_tail_lines = option.output_tail_lines;
+        let mut output_head_lines = option.output_head_lines;
+        let mut env_values = Vec::new();
+        let mut command = Vec::new();
         for (name, value) in option.env.iter() {
-            crate::env::set_var(&name, &value);
+            set_env(&mut env_values, name, value, "[option]");
         }
         
         // 設定ファイルの[options.識別子]を得る
         if let Some(option) = conf.options.get(&identifer) {
+            if let Some(config_command) = &option.command {
+                command = config_command.args();
+            }
             reports.append(&mut option.reports.clone());
             notifies.append(&mut option.notifies.clone());
+            webhooks.append(&mut option.webhooks.clone());
+            mail_to.append(&mut option.mail_to.clone());
+            if option.mail_log.is_some() {
+                mail_log = option.mail_log;
+            }
+            if option.mail_log_lines.is_some() {
+                mail_log_lines = option.mail_log_lines;
+            }
+            on_success.append(&mut option.on_success.clone());
+            on_failure.append(&mut option.on_failure.clone());
+            on_warning.append(&mut option.on_warning.clone());
+            on_timeout.append(&mut option.on_timeout.clone());
+            on_skip.append(&mut option.on_skip.clone());
             if option.cwd.is_some() {
                 cwd = option.cwd.clone();
             }
+            fail_patterns.append(&mut option.fail_patterns.clone());
+            warn_patterns.append(&mut option.warn_patterns.clone());
+            if option.success_exit_codes.is_some() {
+                success_exit_codes = option.success_exit_codes.clone();
+            }
+            if option.warning_exit_codes.is_some() {
+                warning_exit_codes = option.warning_exit_codes.clone();
+            }
+            if option.timeout.is_some() {
+                timeout = option.timeout;
+            }
+            if option.notify_mode.is_some() {
+                notify_mode = option.notify_mode;
+            }
+            if option.report_cooldown.is_some() {
+                report_cooldown = option.report_cooldown;
+            }
+            if option.max_reports_per_hour.is_some() {
+                max_reports_per_hour = option.max_reports_per_hour;
+            }
+            if option.report_timeout.is_some() {
+                report_timeout = option.report_timeout;
+            }
+            if option.fail_on_report_error.is_some() {
+                fail_on_report_error = option.fail_on_report_error;
+            }
+            if option.report_mode.is_some() {
+                report_mode = option.report_mode;
+            }
+            if option.report_log.is_some() {
+                report_log = option.report_log;
+            }
+            if option.output_tail_lines.is_some() {
+                output_tail_lines = option.output_tail_lines;
+            }
+            if option.output_head_lines.is_some() {
+                output_head_lines = option.output_head_lines;
+            }
             for (name, value) in option.env.iter() {
-                crate::env::set_var(&name, &value);
+                set_env(&mut env_values, name, value, &format!("[options.{}]", identifer));
             }
         }
         
         // オプションに制定された分を追加する
-        reports.append(&mut matches.opt_strs("report"));
-        notifies.append(&mut matches.opt_strs("notify"));
+        reports.extend(matches.opt_strs("report").into_iter().map(ConfigReporter::from));
+        notifies.extend(matches.opt_strs("notify").into_iter().map(ConfigReporter::from));
+        on_success.extend(matches.opt_strs("on-success").into_iter().map(ConfigReporter::from));
+        on_failure.extend(matches.opt_strs("on-failure").into_iter().map(ConfigReporter::from));
+        on_warning.extend(matches.opt_strs("on-warning").into_iter().map(ConfigReporter::from));
+        on_timeout.extend(matches.opt_strs("on-timeout").into_iter().map(ConfigReporter::from));
+        on_skip.extend(matches.opt_strs("on-skip").into_iter().map(ConfigReporter::from));
 
         // オプションから環境変数をセット
         for env in matches.opt_strs("env") {
             let Some(pos) = env.find("=") else { continue; };
             let name = &env[0..pos];
             let value = if pos < env.len() { &env[pos+1..] } else { "" };
-            crate::env::set_var(name, value);
+            set_env(&mut env_values, name, value, "--env");
         }
 
         // オプションに指定されていれば、オプションの値を有効にする
@@ -71,31 +251,64 @@ impl EzCron {
         if matches.opt_str("cwd").is_some() {
             cwd = matches.opt_str("cwd");
         }
+        if let Some(value) = matches.opt_str("timeout") {
+            timeout = Some(value.parse()?);
+        }
+
         // カレントディレクトリの環境変数を展開する
-        cwd = match  cwd {
-            None => None,
-            Some(value) => Some(crate::env::change_var(&value)),
-        };
+        cwd = cwd.map(|value| crate::env::change_var(&value));
+
+        // 出力のパターンをコンパイルする
+        let fail_patterns = fail_patterns.iter()
+            .map(|pattern| Regex::new(pattern))
+            .collect::<Result<Vec<Regex>, _>>()?;
+        let warn_patterns = warn_patterns.iter()
+            .map(|pattern| Regex::new(pattern))
+            .collect::<Result<Vec<Regex>, _>>()?;
 
         // 構造体に値をセット
         Ok(Self {
+            state_dir: conf.ezcron.state_dir(),
             log_dir: conf.ezcron.log_dir,
             pid_dir: conf.ezcron.pid_dir,
             identifer: identifer.clone(),
-            reports: reports,
-            notifies: notifies,
-            cwd: cwd,
+            smtp: conf.ezcron.smtp,
+            reports,
+            notifies,
+            webhooks,
+            mail_to,
+            mail_log: mail_log.unwrap_or_default(),
+            mail_log_lines: mail_log_lines.unwrap_or(mail::DEFAULT_LOG_LINES),
+            on_success,
+            on_failure,
+            on_warning,
+            on_timeout,
+            on_skip,
+            cwd,
             multipled: matches.opt_present("multipled"),
+            timeout,
+            timeout_grace: TIMEOUT_GRACE,
+            notify_mode: notify_mode.unwrap_or_default(),
+            report_cooldown,
+            max_reports_per_hour,
+            report_timeout: report_timeout.unwrap_or(DEFAULT_REPORT_TIMEOUT),
+            fail_on_report_error: fail_on_report_error.unwrap_or(false),
+            report_mode: report_mode.unwrap_or_default(),
+            report_log: report_log.unwrap_or_default(),
+            output_tail_lines: output_tail_lines.unwrap_or(DEFAULT_OUTPUT_TAIL_LINES),
+            output_head_lines: output_head_lines.unwrap_or(0),
+            spool_dir: conf.ezcron.spool_dir,
+            spool_retry_interval: conf.ezcron.spool_retry_interval.unwrap_or(spool::DEFAULT_RETRY_INTERVAL),
+            history_max_lines: conf.ezcron.history_max_lines.unwrap_or(history::DEFAULT_MAX_LINES),
+            fail_patterns,
+            warn_patterns,
+            success_exit_codes: success_exit_codes.unwrap_or(vec![0]),
+            warning_exit_codes: warning_exit_codes.unwrap_or_default(),
+            env: env_values,
+            command,
         })
     }
-    fn do_exec(&self, args: &[String], logger: &mut Logger) -> Result<Option<Report>, Box<dyn std::error::Error>> {
-        // pidファイルの作成
-        let mut pid_file = pid::Pid::new(&self.identifer, self.multipled, &self.pid_dir);
-        if pid_file.is_exists() {
-            // 同時実行を許可していなく、既に実行済であればリターン
-            return Ok(None);
-        }
-    
+    fn do_exec(&self, args: &[String], logger: &mut Logger) -> Result<Report, Box<dyn std::error::Error>> {
         // レポートの作成
         let mut report = Report {
             identifer: self.identifer.to_string(),
@@ -104,6 +317,18 @@ impl EzCron {
             log: logger.path.clone(),
             ..Default::default()
         };
+
+        // pidファイルの作成
+        let mut pid_file = pid::Pid::new(&self.identifer, self.multipled, &self.pid_dir);
+        if pid_file.is_exists() {
+            // 同時実行を許可していなく、既に実行済であればスキップする
+            report.result = "process is already running".to_string();
+            report.status = ReportStatus::Finished;
+            report.outcome = Some(ReportOutcome::Skipped);
+            report.end_at = Some(Local::now());
+            logger.write(&report.result)?;
+            return Ok(report);
+        }
     
         // パイプの作成
         let (r, w) = posix::pipe()?;
@@ -115,7 +340,9 @@ impl EzCron {
         let config = PopenConfig {
             stdout: Redirection::File(w.try_clone()?),
             stderr: Redirection::File(w.try_clone()?),
-            cwd: self.cwd.clone().map_or(None, |s| Some(s.into())),
+            cwd: self.cwd.clone().map(|s| s.into()),
+            // プロセスグループ単位で停止、再開できるようにする
+            setpgid: true,
             ..Default::default()
         };
         drop(w);
@@ -127,44 +354,132 @@ impl EzCron {
                 report.result = format!("process execute error! '{}'", err);
                 report.exitcode = 127;
                 report.status = ReportStatus::Finished;
+                report.outcome = Some(ReportOutcome::Failure);
                 report.end_at = Some(Local::now());
                 logger.write("--------")?;
                 logger.write(&report.result)?;
-                return Ok(Some(report));
+                return Ok(report);
             },
         };
     
-        // pidファイルの書き込み
+        // pidファイルの書き込み(実行中の情報が無いpidファイルとして扱われないよう先に書き込む)
         report.pid = popen.pid().unwrap_or(0);
+        if let Err(err) = pid_file.write_info(&RunInfo::from(&report)) {
+            logger.write(&format!("run info saving error! '{}'", err))?;
+        }
         pid_file.touch(popen.pid().unwrap_or(0))?;
 
         // 開始を通知する
         report.result = format!("start program! '{}'", args.join(" "));
         self.do_notify(&report, logger)?;
+        let usage = posix::rusage_children().ok();
     
         // プロセス開始をログに記録する
         logger.write(&report.result)?;
         logger.write("--------")?;
+
+        // 受け取ったシグナルの転送と、制限時間を監視する
+        let (tx, rx) = mpsc::channel::<()>();
+        let watcher = {
+            let pid = report.pid;
+            let timeout = self.timeout.map(Duration::from_secs);
+            let grace = Duration::from_secs(self.timeout_grace);
+            thread::spawn(move || {
+                let started = Instant::now();
+                let mut terminated_at: Option<Instant> = None;
+                let mut killed = false;
+                while rx.recv_timeout(Duration::from_millis(WATCH_INTERVAL)) == Err(RecvTimeoutError::Timeout) {
+                    // ジョブは別のプロセスグループのため、ezcronが受け取ったSIGINT、SIGTERMを転送する
+                    for sig in [libc::SIGINT, libc::SIGTERM] {
+                        if posix::take_signal(sig) {
+                            posix::kill_group(pid, sig).ok();
+                        }
+                    }
+                    // 制限時間を過ぎたら子孫のプロセスも終了させるためプロセスグループに送る
+                    match terminated_at {
+                        None if timeout.is_some_and(|timeout| started.elapsed() >= timeout) => {
+                            if posix::kill_group(pid, libc::SIGTERM).is_err() {
+                                return false;
+                            }
+                            terminated_at = Some(Instant::now());
+                        },
+                        // 猶予時間を過ぎても終了しなければSIGKILLを送る
+                        Some(terminated_at) if !killed && terminated_at.elapsed() >= grace => {
+                            posix::kill_group(pid, libc::SIGKILL).ok();
+                            killed = true;
+                        },
+                        _ => (),
+                    }
+                }
+                terminated_at.is_some()
+            })
+        };
     
         // 標準出力、標準エラーをログファイルに書き込み
-        let br = BufReader::new(r);
-        for line in br.lines() {
-            if let Ok(line) = line {
-                logger.write(&line)?;
+        let mut br = BufReader::new(r);
+        let mut output_tail = VecDeque::with_capacity(self.output_tail_lines);
+        let mut buf = Vec::new();
+        loop {
+            // UTF-8で無い行も読み飛ばさずに置き換えて扱う
+            buf.clear();
+            let read = match br.read_until(b'\n', &mut buf) {
+                Ok(0) => break,
+                Ok(read) => read,
+                Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            };
+            if buf.last() == Some(&b'\n') {
+                buf.pop();
+                if buf.last() == Some(&b'\r') {
+                    buf.pop();
+                }
+            }
+            let line = String::from_utf8_lossy(&buf).into_owned();
+            logger.write(&line)?;
+            // 出力の先頭、末尾の行と、行数、バイト数(変換前の改行を含む)を記録する
+            report.output_lines += 1;
+            report.output_bytes += read as u64;
+            if report.output_head.len() < self.output_head_lines {
+                report.output_head.push(line.clone());
+            }
+            if self.output_tail_lines > 0 {
+                if output_tail.len() >= self.output_tail_lines {
+                    output_tail.pop_front();
+                }
+                output_tail.push_back(line.clone());
+            }
+            // 出力内容をパターンと照合する
+            if self.fail_patterns.iter().any(|re| re.is_match(&line)) {
+                report.fail_matches.push(ReportMatch { line: logger.lines, text: line });
+            } else if self.warn_patterns.iter().any(|re| re.is_match(&line)) {
+                report.warn_matches.push(ReportMatch { line: logger.lines, text: line });
             }
         }
     
-        // プロセス終了まで待つ
-        let Ok(status) = popen.wait() else {
+        report.output_tail = output_tail.into();
+
+        // プロセス終了まで待ち、制限時間の監視を終了する
+        let status = popen.wait();
+        drop(tx);
+        let timed_out = watcher.join().unwrap_or(false);
+        let Ok(status) = status else {
             report.result = "process wait error".to_string();
             report.exitcode = 128;
             report.status = ReportStatus::Finished;
+            report.outcome = Some(ReportOutcome::Failure);
             report.end_at = Some(Local::now());
             logger.write("--------")?;
             logger.write(&report.result)?;
-            return Ok(Some(report));
+            return Ok(report);
         };
     
+        // 資源使用量を記録する
+        report.usage = usage.zip(posix::rusage_children().ok())
+            .map(|(before, after)| after.since(&before));
+
+        // 他のプロセスから行われた操作を記録する
+        report.actions = pid_file.actions();
+
         // 終了処理
         logger.write("--------")?;
         report.end_at = Some(Local::now());
@@ -184,9 +499,42 @@ impl EzCron {
             _ => (),
         };
         report.status = ReportStatus::Finished;
+        report.outcome = Some(self.outcome(&status));
         logger.write(&report.result)?;
+
+        // 制限時間を過ぎていればタイムアウトとする
+        if timed_out {
+            report.outcome = Some(ReportOutcome::Timeout);
+            logger.write(&format!("process timed out after {} seconds", self.timeout.unwrap_or(0)))?;
+        }
+
+        // パターンに一致した出力があれば結果を変更する
+        if !report.fail_matches.is_empty() {
+            if report.outcome != Some(ReportOutcome::Timeout) {
+                report.outcome = Some(ReportOutcome::Failure);
+            }
+            logger.write(&format!("output matched fail patterns({} lines)", report.fail_matches.len()))?;
+        } else if !report.warn_matches.is_empty() {
+            if report.outcome == Some(ReportOutcome::Success) {
+                report.outcome = Some(ReportOutcome::Warning);
+            }
+            logger.write(&format!("output matched warn patterns({} lines)", report.warn_matches.len()))?;
+        }
     
-        Ok(Some(report))
+        Ok(report)
+    }
+    fn outcome(&self, status: &ExitStatus) -> ReportOutcome {
+        // 終了コードから結果を判定する
+        let ExitStatus::Exited(code) = status else {
+            return ReportOutcome::Failure;
+        };
+        if self.success_exit_codes.contains(code) {
+            ReportOutcome::Success
+        } else if self.warning_exit_codes.contains(code) {
+            ReportOutcome::Warning
+        } else {
+            ReportOutcome::Failure
+        }
     }
     fn do_notify(&self, report: &Report, logger: &mut Logger) -> Result<(), Box<dyn std::error::Error>> {
         let mut doing = false;
@@ -195,12 +543,12 @@ impl EzCron {
                 logger.write("--------")?;
             }
             doing = true;
-            logger.write(&format!("starting notify! '{}'", notify))?;
+            logger.write(&format!("starting notify! '{}'", notify.command))?;
             logger.write("--------")?;
  
-            // プロセスの実行
-            if let Err(_) = execute_report(notify, report, logger) {
-                continue;
+            // プロセスの実行(失敗してもジョブは実行する)
+            if let Err(err) = execute_report(notify, report, &[], notify.timeout.unwrap_or(self.report_timeout), logger) {
+                logger.write(&format!("notify error! '{}'", err))?;
             }
         }
         if doing {
@@ -208,61 +556,546 @@ impl EzCron {
         }
         Ok(())
     }
-    fn do_report(&self, report: &Report, logger: &mut Logger) -> Result<(), Box<dyn std::error::Error>> {
-        for reporter in &self.reports {
+    fn reporters(&self, outcome: Option<ReportOutcome>) -> Vec<&ConfigReporter> {
+        // 結果に応じたスクリプトを選択する
+        let (always, selected) = match outcome {
+            Some(ReportOutcome::Success) => (&self.reports[..], &self.on_success[..]),
+            Some(ReportOutcome::Warning) => (&self.reports[..], &self.on_warning[..]),
+            Some(ReportOutcome::Failure) => (&self.reports[..], &self.on_failure[..]),
+            Some(ReportOutcome::Timeout) => (&self.reports[..], &self.on_timeout[..]),
+            // スキップした場合は"on_skip"のみとする
+            Some(ReportOutcome::Skipped) => (&[][..], &self.on_skip[..]),
+            None => (&self.reports[..], &[][..]),
+        };
+        always.iter().chain(selected.iter()).collect()
+    }
+    fn do_report(&self, report: &Report, logger: &mut Logger) -> Result<Vec<ReporterResult>, Box<dyn std::error::Error>> {
+        // スクリプトの出力が加わる前にログファイルの末尾を得る
+        let reporters = self.reporters(report.outcome);
+        let webhooks = match report.outcome {
+            // スキップした場合は"on_skip"のみとする
+            Some(ReportOutcome::Skipped) => &[][..],
+            _ => &self.webhooks[..],
+        };
+        let templated = reporters.iter().any(|reporter| reporter.template.is_some() || reporter.template_file.is_some())
+            || webhooks.iter().any(|webhook| webhook.template.is_some() || webhook.template_file.is_some());
+        let log_tail = match templated {
+            true => logger::tail(&report.log, template::LOG_TAIL_LINES).unwrap_or_default(),
+            false => Vec::new(),
+        };
+
+        // 通知先の一覧を作成する
+        let deliveries: Vec<Delivery> = reporters.into_iter()
+            .map(|reporter| Delivery::Script {
+                reporter: reporter.clone(),
+                timeout: reporter.timeout.unwrap_or(self.report_timeout),
+            })
+            .chain(webhooks.iter().map(|webhook| Delivery::Webhook { webhook: webhook.clone() }))
+            // スキップした場合はメールを送らない
+            .chain((!self.mail_to.is_empty() && report.outcome != Some(ReportOutcome::Skipped)).then(|| Delivery::Mail {
+                to: self.mail_to.clone(),
+                mail_log: self.mail_log,
+                mail_log_lines: self.mail_log_lines,
+            }))
+            .collect();
+
+        let results: Result<Vec<ReporterResult>, Box<dyn std::error::Error>> = match self.report_mode {
+            ReportMode::Serial => deliveries.iter()
+                .map(|delivery| deliver(delivery, self.smtp.as_ref(), report, &log_tail, logger))
+                .collect(),
+            ReportMode::Parallel => {
+                // 同時に実行し、ログは通知ごとにまとめて書き込む
+                let log_tail = &log_tail;
+                let outputs = thread::scope(|scope| {
+                    let handles: Vec<_> = deliveries.iter().map(|delivery| {
+                        let mut buffered = logger.buffered();
+                        scope.spawn(move || {
+                            let result = deliver(delivery, self.smtp.as_ref(), report, log_tail, &mut buffered)
+                                .map_err(|err| err.to_string());
+                            (result, buffered)
+                        })
+                    }).collect();
+                    handles.into_iter()
+                        .map(|handle| handle.join().map_err(|_| "reporter panicked".to_string()))
+                        .collect::<Vec<_>>()
+                });
+                let mut results = Vec::new();
+                for output in outputs {
+                    let (result, buffered) = output?;
+                    logger.append(buffered)?;
+                    results.push(result?);
+                }
+                Ok(results)
+            },
+            ReportMode::Detached => deliveries.iter()
+                .map(|delivery| match delivery {
+                    Delivery::Script { reporter, .. } => self.detach_report(reporter, report, &log_tail, logger),
+                    _ => deliver(delivery, self.smtp.as_ref(), report, &log_tail, logger),
+                })
+                .collect(),
+        };
+        let results = results?;
+
+        // 失敗した通知をスプールに保存する
+        if let Some(spool_dir) = &self.spool_dir {
+            let now = Local::now();
+            for (delivery, result) in deliveries.into_iter().zip(results.iter()) {
+                if result.success || result.detached {
+                    continue;
+                }
+                let error = result.error.clone()
+                    .or(result.exitcode.map(|code| format!("exit code({})", code)));
+                let entry = SpoolEntry::new(delivery, report.clone(), log_tail.clone(), error, now, self.spool_retry_interval);
+                match spool::push(spool_dir, &entry) {
+                    Ok(path) => logger.write(&format!("report spooled! '{}'", path.display()))?,
+                    Err(err) => logger.write(&format!("spool error! '{}'", err))?,
+                }
+            }
+        }
+        Ok(results)
+    }
+    fn detach_report(&self, reporter: &ConfigReporter, report: &Report, log_tail: &[String], logger: &mut Logger) -> Result<ReporterResult, Box<dyn std::error::Error>> {
+        // 出力先のログファイルを決める
+        let log_path = match self.report_log {
+            ReportLog::Same => logger.path.clone(),
+            ReportLog::Sidecar => sidecar_path(&logger.path),
+        };
+        logger.write("--------")?;
+        logger.write(&format!("starting repot! '{}' detached to '{}'", reporter.command, log_path))?;
+        let started = Instant::now();
+        let result = ReporterResult {
+            detached: true,
+            ..ReporterResult::new(&reporter.command)
+        };
+
+        // 終了を待たずにプロセスを実行する
+        let spawned = report_input(reporter, report, log_tail)
+            .and_then(|input| {
+                let output = OpenOptions::new().create(true).append(true).open(&log_path)?;
+                let stdin = if reporter.input.unwrap_or_default() != ReporterInput::Env {
+                    input_file(&input)?
+                } else {
+                    File::open("/dev/null")?
+                };
+                Ok(report_popen(reporter, report, PopenConfig {
+                    stdin: Redirection::File(stdin),
+                    stdout: Redirection::File(output),
+                    stderr: Redirection::Merge,
+                    detached: true,
+                    ..Default::default()
+                })?)
+            });
+        match spawned {
+            Ok(popen) => {
+                logger.write(&format!("report detached pid({})", popen.pid().unwrap_or(0)))?;
+                Ok(result.succeeded(started))
+            },
+            Err(err) => {
+                logger.write(&format!("process starting error! '{}'", err))?;
+                Ok(result.failed(&err.to_string(), started))
+            },
+        }
+    }
+    fn do_state(&self, report: &mut Report, logger: &mut Logger) -> Result<bool, Box<dyn std::error::Error>> {
+        // 前回の状態を読み込む(失敗しても処理は継続する)
+        let mut state = match State::load(&self.identifer, &self.state_dir) {
+            Ok(state) => state,
+            Err(err) => {
+                logger.write(&format!("state loading error! '{}'", err))?;
+                State::default()
+            },
+        };
+        report.previous_outcome = state.last_outcome;
+        report.consecutive_failures = state.consecutive_failures;
+
+        let Some(outcome) = report.outcome else { return Ok(true); };
+        let (previous, repeated) = if outcome == ReportOutcome::Skipped {
+            // スキップした場合は前回の結果を更新せず、スキップが続いているかで判定する
+            let previous = state.skipped.then_some(ReportOutcome::Skipped);
+            state.skipped = true;
+            (previous, previous.is_some())
+        } else {
+            // 状態を更新する
+            state.update(outcome, report.start_at);
+            report.consecutive_failures = state.consecutive_failures;
+            (report.previous_outcome, report.previous_outcome == Some(outcome))
+        };
+
+        // 通知するかを判定する
+        let now = Local::now();
+        let mut reporting = state::should_report(self.notify_mode, previous, outcome);
+        if !reporting {
+            // 失敗が続いている場合は一定時間ごとに再通知する
+            if let Some(cooldown) = self.report_cooldown {
+                reporting = outcome.is_failure() && state.is_cooled_down(now, cooldown);
+            }
+        }
+        // 結果が変わった場合は制限せず、同じ結果を繰り返し通知する場合のみ制限する
+        let limited = reporting && repeated && state.is_rate_limited(now, self.report_cooldown, self.max_reports_per_hour);
+        if reporting && !limited {
+            report.suppressed_count = state.record_report(now);
+        } else {
+            if limited {
+                state.record_suppressed();
+            }
+            logger.write("--------")?;
+            let previous = previous.map_or("none", |outcome| outcome.as_str());
+            let reason = if limited { "rate limit" } else { "notify mode" };
+            logger.write(&format!("report suppressed by {}! outcome '{}' previous '{}'", reason, outcome.as_str(), previous))?;
+        }
+
+        // 状態を保存する
+        if let Err(err) = state.save(&self.identifer, &self.state_dir) {
+            logger.write(&format!("state saving error! '{}'", err))?;
+        }
+        Ok(reporting && !limited)
+    }
+    fn do_record(&self, report: &Report, results: &[ReporterResult], logger: &mut Logger) -> Result<(), Box<dyn std::error::Error>> {
+        // 通知の結果を状態ファイルに記録する(失敗しても処理は継続する)
+        let saved = State::load(&self.identifer, &self.state_dir)
+            .and_then(|mut state| {
+                state.last_reporters = results.to_vec();
+                state.save(&self.identifer, &self.state_dir)
+            });
+        if let Err(err) = saved {
+            logger.write(&format!("state saving error! '{}'", err))?;
+        }
+
+        // 実行の履歴を追記する
+        let record = RunRecord {
+            report: report.clone(),
+            reporters: results.to_vec(),
+        };
+        if let Err(err) = history::append(&self.state_dir, &record, self.history_max_lines) {
+            logger.write(&format!("history saving error! '{}'", err))?;
+        }
+        Ok(())
+    }
+    fn args(&self, args: &[String]) -> Vec<String> {
+        // 引数が無ければ設定ファイルのコマンドを実行する
+        if args.is_empty() { self.command.clone() } else { args.to_vec() }
+    }
+    pub fn run(&self, args: &[String]) -> Result<i32, Box<dyn std::error::Error>> {
+        let args = self.args(args);
+        if args.is_empty() {
+            return Err(format!("command of '{}' is not specified", self.identifer).into());
+        }
+        let args = &args[..];
+        let mut logger = Logger::new(&self.identifer, &self.log_dir)?;
+        // 実行中に受け取ったSIGINT、SIGTERMはジョブに転送する(無視する設定であればそのままとする)
+        let forwarded = [libc::SIGINT, libc::SIGTERM].into_iter()
+            .filter(|sig| !posix::is_ignored(*sig))
+            .collect::<Vec<libc::c_int>>();
+        for sig in forwarded.iter() {
+            posix::catch_signal(*sig)?;
+        }
+        let report = self.do_exec(args, &mut logger);
+        for sig in forwarded.iter() {
+            posix::default_signal(*sig)?;
+        }
+        let mut report = report?;
+        let mut results = Vec::new();
+        if self.do_state(&mut report, &mut logger)? {
+            results = self.do_report(&report, &mut logger)?;
+        }
+        self.do_record(&report, &results, &mut logger)?;
+
+        // 通知の失敗を終了コードに反映する
+        let failed = results.iter().filter(|result| !result.success).count();
+        if failed > 0 {
+            logger.write("--------")?;
+            logger.write(&format!("report failed({}/{})", failed, results.len()))?;
+            if self.fail_on_report_error {
+                return Ok(EXIT_REPORT_ERROR);
+            }
+        }
+        Ok(0)
+    }  
+    pub fn explain(&self, args: &[String]) -> String {
+        // 実行せずに、設定を反映した結果を表示する
+        let mut text = String::new();
+        let mut line = |name: &str, value: String| text.push_str(&format!("{:<22}{}\n", format!("{}:", name), value));
+        let list = |values: Vec<String>| if values.is_empty() { "-".to_string() } else { values.join("\n                      ") };
+        let commands = |reporters: &[ConfigReporter]| list(reporters.iter().map(|reporter| reporter.command.clone()).collect());
+        let optional = |value: Option<String>| value.unwrap_or("-".to_string());
+        let name = |value: serde_json::Value| value.as_str().unwrap_or_default().to_string();
+
+        line("identifer", self.identifer.clone());
+        let args = self.args(args);
+        line("command", if args.is_empty() { "-".to_string() } else { args.join(" ") });
+        line("cwd", optional(self.cwd.clone()));
+        line("timeout", optional(self.timeout.map(|timeout| format!("{}s", timeout))));
+        line("multipled", self.multipled.to_string());
+        let pid_file = pid::Pid::new(&self.identifer, self.multipled, &self.pid_dir);
+        line("lock", if self.multipled { "- (multipled)".to_string() } else { pid_file.path().display().to_string() });
+        line("log", logger::path(&self.identifer, &self.log_dir, Local::now()).display().to_string());
+        line("state", State::path(&self.identifer, &self.state_dir).display().to_string());
+        line("history", history::path(&self.identifer, &self.state_dir).display().to_string());
+        line("spool_dir", optional(self.spool_dir.clone()));
+        line("reports", commands(&self.reports));
+        line("notifies", commands(&self.notifies));
+        line("on_success", commands(&self.on_success));
+        line("on_failure", commands(&self.on_failure));
+        line("on_warning", commands(&self.on_warning));
+        line("on_timeout", commands(&self.on_timeout));
+        line("on_skip", commands(&self.on_skip));
+        line("webhooks", list(self.webhooks.iter().map(|webhook| format!("{} {}", webhook.method, webhook.url)).collect()));
+        line("mail_to", list(self.mail_to.clone()));
+        line("notify_mode", name(json!(self.notify_mode)));
+        line("report_mode", name(json!(self.report_mode)));
+        line("report_log", name(json!(self.report_log)));
+        line("report_timeout", format!("{}s", self.report_timeout));
+        line("report_cooldown", optional(self.report_cooldown.map(|cooldown| format!("{}s", cooldown))));
+        line("max_reports_per_hour", optional(self.max_reports_per_hour.map(|max| max.to_string())));
+        line("fail_on_report_error", self.fail_on_report_error.to_string());
+        line("fail_patterns", list(self.fail_patterns.iter().map(|pattern| pattern.to_string()).collect()));
+        line("warn_patterns", list(self.warn_patterns.iter().map(|pattern| pattern.to_string()).collect()));
+        line("success_exit_codes", format!("{:?}", self.success_exit_codes));
+        line("warning_exit_codes", format!("{:?}", self.warning_exit_codes));
+        line("output_tail_lines", self.output_tail_lines.to_string());
+        line("output_head_lines", self.output_head_lines.to_string());
+
+        // 環境変数は変更前の値と設定元を表示する
+        line("env", list(self.env.iter().map(|env| {
+            let previous = env.previous.as_ref()
+                .map_or("unset".to_string(), |previous| format!("was '{}'", previous));
+            format!("{}={} ({}, {})", env.name, env.value, env.source, previous)
+        }).collect()));
+        text
+    }
+}
+
+pub fn deliver(delivery: &Delivery, smtp: Option<&ConfigSmtp>, report: &Report, log_tail: &[String], logger: &mut Logger) -> Result<ReporterResult, Box<dyn std::error::Error>> {
+    match delivery {
+        Delivery::Script { reporter, timeout } => {
             logger.write("--------")?;
-            logger.write(&format!("starting repot! '{}'", reporter))?;
+            logger.write(&format!("starting repot! '{}'", reporter.command))?;
             logger.write("--------")?;
  
             // プロセスの実行
-            if let Err(_) = execute_report(reporter, report, logger) {
-                continue;
+            execute_report(reporter, report, log_tail, *timeout, logger)
+        },
+        Delivery::Webhook { webhook } => {
+            logger.write("--------")?;
+            logger.write(&format!("starting webhook! '{} {}'", webhook.method, webhook.url))?;
+            logger.write("--------")?;
+            let started = Instant::now();
+            let result = ReporterResult::new(&webhook.url);
+
+            // 送信する内容を作成する
+            let template = match template::source(&webhook.template, &webhook.template_file) {
+                Ok(template) => template,
+                Err(err) => {
+                    logger.write(&format!("template loading error! '{}'", err))?;
+                    return Ok(result.failed(&err.to_string(), started));
+                },
+            };
+            let body = match (template, webhook.preset) {
+                (Some(template), _) => match template::render(&template, report, log_tail) {
+                    Ok(body) => body,
+                    Err(err) => {
+                        logger.write(&format!("template rendering error! '{}'", err))?;
+                        return Ok(result.failed(&err.to_string(), started));
+                    },
+                },
+                (None, Some(preset)) => {
+                    let lines = webhook.snippet_lines.unwrap_or(preset::DEFAULT_SNIPPET_LINES);
+                    let output_tail = &report.output_tail;
+                    let snippet = preset::snippet(&output_tail[output_tail.len().saturating_sub(lines)..]);
+                    preset::render(preset, report, &snippet).to_string()
+                },
+                (None, None) => serde_json::to_string(&report)?,
+            };
+
+            // HTTPで送信する
+            match webhook::send(webhook, &body, logger) {
+                Ok(()) => Ok(result.succeeded(started)),
+                Err(err) => {
+                    logger.write(&format!("webhook error! '{}'", err))?;
+                    Ok(result.failed(&err.to_string(), started))
+                },
             }
+        },
+        Delivery::Mail { to, mail_log, mail_log_lines } => {
+            logger.write("--------")?;
+            logger.write(&format!("starting mail! '{}'", to.join(", ")))?;
+            logger.write("--------")?;
+            let started = Instant::now();
+            let result = ReporterResult::new(&format!("mailto:{}", to.join(",")));
+
+            // SMTPで送信する
+            let sent = match smtp {
+                Some(smtp) => mail::send(smtp, to, *mail_log, *mail_log_lines, report, logger),
+                None => Err("[ezcron.smtp] is not configured".into()),
+            };
+            match sent {
+                Ok(()) => Ok(result.succeeded(started)),
+                Err(err) => {
+                    logger.write(&format!("mail error! '{}'", err))?;
+                    Ok(result.failed(&err.to_string(), started))
+                },
+            }
+        },
+    }
+}
+
+fn report_input(reporter: &ConfigReporter, report: &Report, log_tail: &[String]) -> Result<String, Box<dyn std::error::Error>> {
+    // 標準入力に渡す内容を作成する(テンプレートが無ければJSON)
+    match template::source(&reporter.template, &reporter.template_file)? {
+        Some(template) => template::render(&template, report, log_tail),
+        None => Ok(serde_json::to_string(&report)?),
+    }
+}
+
+fn report_popen(reporter: &ConfigReporter, report: &Report, config: PopenConfig) -> subprocess::Result<Popen> {
+    // 引数の配列であればシェルを介さずに実行する
+    let argv: Vec<String> = match &reporter.argv {
+        Some(argv) if !argv.is_empty() => argv.iter().map(|arg| report.expand(arg)).collect(),
+        _ => vec!["sh".to_string(), "-c".to_string(), reporter.command.clone()],
+    };
+
+    // 環境変数を設定する
+    let env = (reporter.input.unwrap_or_default() != ReporterInput::Json).then(|| {
+        let mut env = PopenConfig::current_env();
+        env.extend(report.env().into_iter().map(|(name, value)| (name.into(), value.into())));
+        env
+    });
+    Popen::create(&argv, PopenConfig { env, ..config })
+}
+
+fn input_file(input: &str) -> Result<File, Box<dyn std::error::Error>> {
+    // 削除済みの一時ファイルを標準入力として渡す
+    let path = std::env::temp_dir().join(format!("ezcron-{}.input", Uuid::new_v4()));
+    let mut file = OpenOptions::new().read(true).write(true).create_new(true).open(&path)?;
+    fs::remove_file(&path)?;
+    file.write_all(input.as_bytes())?;
+    file.seek(SeekFrom::Start(0))?;
+    Ok(file)
+}
+
+fn sidecar_path(log_path: &str) -> String {
+    // "xxx.log"を"xxx.report.log"にする
+    format!("{}.report.log", log_path.strip_suffix(".log").unwrap_or(log_path))
+}
+
+fn terminate_group(popen: &mut Popen) {
+    // プロセスグループにSIGTERMを送り、猶予時間を過ぎても残っていればSIGKILLを送る
+    let Some(pid) = popen.pid() else { return; };
+    if posix::kill_group(pid, libc::SIGTERM).is_err() {
+        return;
+    }
+    let started = Instant::now();
+    while started.elapsed() < Duration::from_secs(TIMEOUT_GRACE) {
+        // 終了したスクリプトを回収してから、残っているプロセスを確認する
+        popen.poll();
+        if !status::is_group_alive(pid) {
+            return;
         }
-        Ok(())
+        thread::sleep(Duration::from_millis(WATCH_INTERVAL));
     }
-    pub fn run(&self, args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
-        let mut logger = Logger::new(&self.identifer, &self.log_dir)?;
-        let Some(report) = self.do_exec(&args, &mut logger)? else { return Ok(()); };
-        self.do_report(&report, &mut logger)?;
-        Ok(())
-    }  
+    posix::kill_group(pid, libc::SIGKILL).ok();
 }
 
-fn execute_report(shell: &str, report: &Report, logger: &mut Logger) -> Result<(), Box<dyn std::error::Error>> {
-    let json: &str = &serde_json::to_string(&report)?;
+fn execute_report(reporter: &ConfigReporter, report: &Report, log_tail: &[String], timeout: u64, logger: &mut Logger) -> Result<ReporterResult, Box<dyn std::error::Error>> {
+    let started = Instant::now();
+    let mut result = ReporterResult::new(&reporter.command);
 
-    // プロセスの実行
-    let out = match Exec::shell(shell)
-        .stdin(json)
-        .stdout(Redirection::Pipe)
-        .stderr(Redirection::Merge)
-        .capture() {
-            Ok(out) => out,
+    // 標準入力に渡す内容を作成する
+    let input = match report_input(reporter, report, log_tail) {
+        Ok(input) => input,
+        Err(err) => {
+            logger.write(&format!("template error! '{}'", err))?;
+            return Ok(result.failed(&err.to_string(), started));
+        },
+    };
+
+    // 標準入力を設定する
+    let (stdin, input) = if reporter.input.unwrap_or_default() != ReporterInput::Env {
+        (Redirection::Pipe, Some(input.into_bytes()))
+    } else {
+        match File::open("/dev/null") {
+            Ok(null) => (Redirection::File(null), None),
             Err(err) => {
                 logger.write(&format!("process starting error! '{}'", err))?;
-                return Err(Box::new(err));
+                return Ok(result.failed(&err.to_string(), started));
             },
-        };
+        }
+    };
+
+    // プロセスの実行(子孫のプロセスもまとめて終了させられるようプロセスグループを分ける)
+    let config = PopenConfig {
+        stdin,
+        stdout: Redirection::Pipe,
+        stderr: Redirection::Merge,
+        setpgid: true,
+        ..Default::default()
+    };
+    let mut popen = match report_popen(reporter, report, config) {
+        Ok(popen) => popen,
+        Err(err) => {
+            logger.write(&format!("process starting error! '{}'", err))?;
+            return Ok(result.failed(&err.to_string(), started));
+        },
+    };
+
+    // 制限時間まで出力を読み込む
+    let output = match popen.communicate_start(input).limit_time(Duration::from_secs(timeout)).read() {
+        Ok((output, _)) => output.unwrap_or_default(),
+        Err(err) => {
+            if err.kind() == std::io::ErrorKind::TimedOut {
+                result.timed_out = true;
+                terminate_group(&mut popen);
+            } else if err.kind() == std::io::ErrorKind::BrokenPipe {
+                // 標準入力を読まずに終了したスクリプトは終了コードで判定する
+            } else {
+                result.error = Some(err.to_string());
+            }
+            err.capture.0.unwrap_or_default()
+        },
+    };
 
     // 結果をログに書き込む
-    for (_, line) in out.stdout_str().lines().enumerate() {
-        logger.write(&line)?;
+    for line in String::from_utf8_lossy(&output).lines() {
+        logger.write(line)?;
     }
 
-    Ok(())
+    // 終了を待ち、終了コードと所要時間を記録する
+    result.exitcode = match popen.wait()? {
+        ExitStatus::Exited(code) => Some(code),
+        ExitStatus::Signaled(sig) => Some(sig as u32 + 128),
+        ExitStatus::Other(code) => Some(code as u32),
+        _ => None,
+    };
+    result.duration = started.elapsed().as_secs_f64();
+    result.success = !result.timed_out && result.error.is_none() && result.exitcode == Some(0);
+    if result.timed_out {
+        logger.write(&format!("report timed out after {} seconds", timeout))?;
+    } else {
+        logger.write(&format!("report terminated code({}) duration({:.3}s)", result.exitcode.unwrap_or(0), result.duration))?;
+    }
+    Ok(result)
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::useless_vec, clippy::unused_io_amount, clippy::redundant_field_names)]
 mod tests {
     use std::collections::HashMap;
     use std::fs::File;
     use std::io::Write;
     use std::path::{Path, PathBuf};
+    use std::thread;
+    use std::time::{Duration, Instant};
     use registorder_map::RegistOrderMap;
-    use crate::config::{Config, ConfigEzCron, ConfigOption};
-    use crate::ezcron::EzCron;
+    use crate::config::{Config, ConfigCommand, ConfigEzCron, ConfigOption, ConfigReporter, ConfigSmtp, ReporterInput, ConfigWebhook, MailLog, NotifyMode, Preset, ReportLog, ReportMode};
+    use crate::config;
+    use crate::control;
+    use crate::ezcron::{execute_report, EzCron, EXIT_REPORT_ERROR};
+    use crate::history;
+    use crate::logger::Logger;
     use crate::parse_args;
+    use crate::report::{Report, ReportOutcome, ReportStatus};
+    use crate::spool;
+    use crate::state::State;
 
     struct TestConfigFile {
         path: Box<PathBuf>,
@@ -288,6 +1121,28 @@ mod tests {
         }
     }
 
+    struct TestDir {
+        path: Box<PathBuf>,
+    }
+
+    impl TestDir {
+        fn new(path: &str) -> Self {
+            let path = Path::new(path);
+            std::fs::create_dir_all(path).unwrap();
+            Self {
+                path: Box::new(path.to_path_buf()),
+            }
+        }
+    }
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            if self.path.is_dir() {
+                std::fs::remove_dir_all(self.path.as_path()).unwrap();
+            }
+        }
+    }
+
     #[test]
     // 一通り設定した場合の正常性を確認する
     fn test_ezcron_basic() {
@@ -309,6 +1164,7 @@ mod tests {
             ezcron: ConfigEzCron {
                 log_dir: "var/log/ezcron".to_string(),
                 pid_dir: "run/ezcron".to_string(),
+                ..Default::default()
             },
             option: None,
             options: HashMap::new(),
@@ -346,12 +1202,14 @@ mod tests {
             ezcron: ConfigEzCron {
                 log_dir: "var/log/ezcron".to_string(),
                 pid_dir: "run/ezcron".to_string(),
+                ..Default::default()
             },
             option: Some(ConfigOption {
-                reports: vec!["report00.sh".to_string()],
-                notifies: vec!["notify00.sh".to_string()],
+                reports: vec!["report00.sh".into()],
+                notifies: vec!["notify00.sh".into()],
                 cwd: Some("/path/to/base".to_string()),
                 env: RegistOrderMap::new(),
+                ..ConfigOption::new()
             }),
             options: HashMap::new(),
         };
@@ -385,12 +1243,14 @@ mod tests {
             ezcron: ConfigEzCron {
                 log_dir: "var/log/ezcron".to_string(),
                 pid_dir: "run/ezcron".to_string(),
+                ..Default::default()
             },
             option: Some(ConfigOption {
-                reports: vec!["report00.sh".to_string()],
-                notifies: vec!["notify00.sh".to_string()],
+                reports: vec!["report00.sh".into()],
+                notifies: vec!["notify00.sh".into()],
                 cwd: Some("/path/to/base".to_string()),
                 env: env,
+                ..ConfigOption::new()
             }),
             options: HashMap::new(),
         };
@@ -424,15 +1284,17 @@ mod tests {
         env.insert("TEST02".to_string(), "VALUE2".to_string());
         let mut options = HashMap::new();
         options.insert("test".to_string(), ConfigOption {
-            reports: vec!["report00.sh".to_string()],
-            notifies: vec!["notify00.sh".to_string()],
+            reports: vec!["report00.sh".into()],
+            notifies: vec!["notify00.sh".into()],
             cwd: Some("/path/to/base".to_string()),
             env: env,
+            ..ConfigOption::new()
         });
         let test_config = Config {
             ezcron: ConfigEzCron {
                 log_dir: "var/log/ezcron".to_string(),
                 pid_dir: "run/ezcron".to_string(),
+                ..Default::default()
             },
             option: None,
             options: options,
@@ -467,6 +1329,7 @@ mod tests {
             ezcron: ConfigEzCron {
                 log_dir: "var/log/ezcron".to_string(),
                 pid_dir: "run/ezcron".to_string(),
+                ..Default::default()
             },
             option: None,
             options: HashMap::new(),
@@ -475,4 +1338,1125 @@ mod tests {
         let main = EzCron::new(&matches).unwrap();
         assert_eq!(main.cwd, Some("BBB".to_string()));
     }    
+
+    #[test]
+    // 出力がパターンに一致した場合に状態が変わるか確認する
+    fn test_ezcron_patterns() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_patterns.toml",
+            "test", "--", "sh", "-c", "echo WARN: aaa; echo ERROR: bbb; echo WARN: ccc"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        let mut options = HashMap::new();
+        options.insert("test".to_string(), ConfigOption {
+            warn_patterns: vec!["^WARN:".to_string()],
+            ..ConfigOption::new()
+        });
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_patterns".to_string(),
+                pid_dir: "test_ezcron_patterns".to_string(),
+                ..Default::default()
+            },
+            option: Some(ConfigOption {
+                fail_patterns: vec!["^ERROR:".to_string()],
+                ..ConfigOption::new()
+            }),
+            options,
+        };
+        let _test_dir = TestDir::new("test_ezcron_patterns");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_patterns.toml", &test_config);
+        let main = EzCron::new(&matches).unwrap();
+        assert_eq!(main.fail_patterns.len(), 1);
+        assert_eq!(main.warn_patterns.len(), 1);
+        let mut logger = Logger::new(&main.identifer, &main.log_dir).unwrap();
+        let report = main.do_exec(&args, &mut logger).unwrap();
+        assert!(matches!(report.status, ReportStatus::Finished));
+        assert_eq!(report.outcome, Some(ReportOutcome::Failure));
+        assert_eq!(report.fail_matches.len(), 1);
+        assert_eq!(report.fail_matches[0].line, 4);
+        assert_eq!(report.fail_matches[0].text, "ERROR: bbb");
+        assert_eq!(report.warn_matches.len(), 2);
+        assert_eq!(report.warn_matches[0].line, 3);
+        assert_eq!(report.warn_matches[1].line, 5);
+    }
+
+    #[test]
+    // UTF-8で無い出力の後の行もパターンと照合するか確認する
+    fn test_ezcron_patterns_non_utf8() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_patterns_non_utf8.toml",
+            "test", "--", "sh", "-c", "printf '\\377\\n'; echo ERROR: after"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_patterns_non_utf8".to_string(),
+                pid_dir: "test_ezcron_patterns_non_utf8".to_string(),
+                ..Default::default()
+            },
+            option: Some(ConfigOption {
+                fail_patterns: vec!["^ERROR:".to_string()],
+                ..ConfigOption::new()
+            }),
+            options: HashMap::new(),
+        };
+        let _test_dir = TestDir::new("test_ezcron_patterns_non_utf8");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_patterns_non_utf8.toml", &test_config);
+        let main = EzCron::new(&matches).unwrap();
+        let mut logger = Logger::new(&main.identifer, &main.log_dir).unwrap();
+        let report = main.do_exec(&args, &mut logger).unwrap();
+        assert_eq!(report.outcome, Some(ReportOutcome::Failure));
+        assert_eq!(report.output_lines, 2);
+        assert_eq!(report.fail_matches.len(), 1);
+        assert_eq!(report.fail_matches[0].text, "ERROR: after");
+    }
+
+    #[test]
+    // 正規表現が不正であればエラーになるか確認する
+    fn test_ezcron_patterns_invalid() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_patterns_invalid.toml",
+            "test", "--", "ls", "-al"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, _))) = parse_args(&mut args) else { panic!("impossible error") };
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "var/log/ezcron".to_string(),
+                pid_dir: "run/ezcron".to_string(),
+                ..Default::default()
+            },
+            option: Some(ConfigOption {
+                fail_patterns: vec!["(ERROR".to_string()],
+                ..ConfigOption::new()
+            }),
+            options: HashMap::new(),
+        };
+        let _test_config_file = TestConfigFile::new("./test_ezcron_patterns_invalid.toml", &test_config);
+        assert!(EzCron::new(&matches).is_err());
+    }
+
+    #[test]
+    // 終了コードから結果が判定されるか確認する
+    fn test_ezcron_exit_codes() {
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_exit_codes".to_string(),
+                pid_dir: "test_ezcron_exit_codes".to_string(),
+                ..Default::default()
+            },
+            option: Some(ConfigOption {
+                success_exit_codes: Some(vec![0, 1]),
+                warning_exit_codes: Some(vec![2]),
+                ..ConfigOption::new()
+            }),
+            options: HashMap::new(),
+        };
+        let _test_dir = TestDir::new("test_ezcron_exit_codes");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_exit_codes.toml", &test_config);
+        for (code, outcome) in [(0, ReportOutcome::Success), (1, ReportOutcome::Success), (2, ReportOutcome::Warning), (3, ReportOutcome::Failure)] {
+            let mut args = ["program",
+                "-c", "./test_ezcron_exit_codes.toml",
+                "test", "--", "sh", "-c", &format!("exit {}", code)
+            ].iter().map(|&s| s.to_string()).collect();
+            let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+            let main = EzCron::new(&matches).unwrap();
+            let mut logger = Logger::new(&main.identifer, &main.log_dir).unwrap();
+            let report = main.do_exec(&args, &mut logger).unwrap();
+            assert_eq!(report.exitcode, code);
+            assert_eq!(report.outcome, Some(outcome));
+        }
+    }
+
+    #[test]
+    // 結果に応じたスクリプトが選択されるか確認する
+    fn test_ezcron_reporters() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_reporters.toml",
+            "-r", "report01.sh",
+            "--on-success", "success01.sh",
+            "--on-warning", "warning01.sh",
+            "--on-timeout", "timeout01.sh",
+            "--on-skip", "skip01.sh",
+            "test", "--", "ls", "-al"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, _))) = parse_args(&mut args) else { panic!("impossible error") };
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "var/log/ezcron".to_string(),
+                pid_dir: "run/ezcron".to_string(),
+                ..Default::default()
+            },
+            option: Some(ConfigOption {
+                on_failure: vec!["failure00.sh".into()],
+                ..ConfigOption::new()
+            }),
+            options: HashMap::new(),
+        };
+        let _test_config_file = TestConfigFile::new("./test_ezcron_reporters.toml", &test_config);
+        let main = EzCron::new(&matches).unwrap();
+        assert_eq!(main.reporters(Some(ReportOutcome::Success)), vec!["report01.sh", "success01.sh"]);
+        assert_eq!(main.reporters(Some(ReportOutcome::Warning)), vec!["report01.sh", "warning01.sh"]);
+        assert_eq!(main.reporters(Some(ReportOutcome::Failure)), vec!["report01.sh", "failure00.sh"]);
+        assert_eq!(main.reporters(Some(ReportOutcome::Timeout)), vec!["report01.sh", "timeout01.sh"]);
+        assert_eq!(main.reporters(Some(ReportOutcome::Skipped)), vec!["skip01.sh"]);
+    }
+
+    #[test]
+    // 実行中であればスキップされるか確認する
+    fn test_ezcron_skip() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_skip.toml",
+            "test", "--", "ls", "-al"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_skip".to_string(),
+                pid_dir: "test_ezcron_skip".to_string(),
+                ..Default::default()
+            },
+            option: None,
+            options: HashMap::new(),
+        };
+        let _test_dir = TestDir::new("test_ezcron_skip");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_skip.toml", &test_config);
+        std::fs::write("test_ezcron_skip/test.pid", "100").unwrap();
+        let main = EzCron::new(&matches).unwrap();
+        let mut logger = Logger::new(&main.identifer, &main.log_dir).unwrap();
+        let report = main.do_exec(&args, &mut logger).unwrap();
+        assert_eq!(report.outcome, Some(ReportOutcome::Skipped));
+        assert_eq!(report.pid, 0);
+    }
+
+    #[test]
+    // 制限時間を過ぎた場合にタイムアウトとなるか確認する
+    fn test_ezcron_timeout() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_timeout.toml",
+            "-t", "1",
+            "test", "--", "sleep", "10"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_timeout".to_string(),
+                pid_dir: "test_ezcron_timeout".to_string(),
+                ..Default::default()
+            },
+            option: None,
+            options: HashMap::new(),
+        };
+        let _test_dir = TestDir::new("test_ezcron_timeout");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_timeout.toml", &test_config);
+        let main = EzCron::new(&matches).unwrap();
+        assert_eq!(main.timeout, Some(1));
+        let mut logger = Logger::new(&main.identifer, &main.log_dir).unwrap();
+        let report = main.do_exec(&args, &mut logger).unwrap();
+        assert_eq!(report.outcome, Some(ReportOutcome::Timeout));
+        assert_eq!(report.exitcode, 128 + 15);
+    }
+
+    #[test]
+    // 制限時間を過ぎた場合に子孫のプロセスも終了させるか確認する
+    fn test_ezcron_timeout_group() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_timeout_group.toml",
+            "-t", "1",
+            "test", "--", "sh", "-c", "sleep 6; echo done"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_timeout_group".to_string(),
+                pid_dir: "test_ezcron_timeout_group".to_string(),
+                ..Default::default()
+            },
+            option: None,
+            options: HashMap::new(),
+        };
+        let _test_dir = TestDir::new("test_ezcron_timeout_group");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_timeout_group.toml", &test_config);
+        let main = EzCron::new(&matches).unwrap();
+        let mut logger = Logger::new(&main.identifer, &main.log_dir).unwrap();
+        let started = Instant::now();
+        let report = main.do_exec(&args, &mut logger).unwrap();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(report.outcome, Some(ReportOutcome::Timeout));
+        assert_eq!(report.output_lines, 0);
+    }
+
+    #[test]
+    // SIGTERMで終了しない場合にSIGKILLを送るか確認する
+    fn test_ezcron_timeout_kill() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_timeout_kill.toml",
+            "-t", "1",
+            "test", "--", "sh", "-c", "trap '' TERM; sleep 30"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_timeout_kill".to_string(),
+                pid_dir: "test_ezcron_timeout_kill".to_string(),
+                ..Default::default()
+            },
+            option: None,
+            options: HashMap::new(),
+        };
+        let _test_dir = TestDir::new("test_ezcron_timeout_kill");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_timeout_kill.toml", &test_config);
+        let mut main = EzCron::new(&matches).unwrap();
+        main.timeout_grace = 1;
+        let mut logger = Logger::new(&main.identifer, &main.log_dir).unwrap();
+        let started = Instant::now();
+        let report = main.do_exec(&args, &mut logger).unwrap();
+        assert!(started.elapsed() < Duration::from_secs(10));
+        assert_eq!(report.outcome, Some(ReportOutcome::Timeout));
+        assert_eq!(report.exitcode, 128 + 9);
+    }
+
+    #[test]
+    // 結果が変わった場合のみ通知されるか確認する
+    fn test_ezcron_notify_mode() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_notify_mode.toml",
+            "test", "--", "sh", "-c", "exit 1"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_notify_mode".to_string(),
+                pid_dir: "test_ezcron_notify_mode".to_string(),
+                state_dir: Some("test_ezcron_notify_mode".to_string()),
+                ..Default::default()
+            },
+            option: Some(ConfigOption {
+                notify_mode: Some(NotifyMode::Change),
+                ..ConfigOption::new()
+            }),
+            options: HashMap::new(),
+        };
+        let _test_dir = TestDir::new("test_ezcron_notify_mode");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_notify_mode.toml", &test_config);
+        let main = EzCron::new(&matches).unwrap();
+        assert_eq!(main.notify_mode, NotifyMode::Change);
+        let mut logger = Logger::new(&main.identifer, &main.log_dir).unwrap();
+        let mut report = main.do_exec(&args, &mut logger).unwrap();
+        assert!(main.do_state(&mut report, &mut logger).unwrap());
+        assert_eq!(report.previous_outcome, None);
+        assert_eq!(report.consecutive_failures, 1);
+        let mut report = main.do_exec(&args, &mut logger).unwrap();
+        assert!(!main.do_state(&mut report, &mut logger).unwrap());
+        assert_eq!(report.previous_outcome, Some(ReportOutcome::Failure));
+        assert_eq!(report.consecutive_failures, 2);
+        // notify_modeで抑止した場合は抑止した回数に含めない
+        let state = State::load("test", "test_ezcron_notify_mode").unwrap();
+        assert_eq!(state.suppressed_count, 0);
+    }
+
+    #[test]
+    // 通知の回数が制限されるか確認する
+    fn test_ezcron_rate_limit() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_rate_limit.toml",
+            "test", "--", "sh", "-c", "exit 1"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_rate_limit".to_string(),
+                pid_dir: "test_ezcron_rate_limit".to_string(),
+                state_dir: Some("test_ezcron_rate_limit".to_string()),
+                ..Default::default()
+            },
+            option: Some(ConfigOption {
+                max_reports_per_hour: Some(1),
+                ..ConfigOption::new()
+            }),
+            options: HashMap::new(),
+        };
+        let _test_dir = TestDir::new("test_ezcron_rate_limit");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_rate_limit.toml", &test_config);
+        let main = EzCron::new(&matches).unwrap();
+        let mut logger = Logger::new(&main.identifer, &main.log_dir).unwrap();
+        let mut report = main.do_exec(&args, &mut logger).unwrap();
+        assert!(main.do_state(&mut report, &mut logger).unwrap());
+        let mut report = main.do_exec(&args, &mut logger).unwrap();
+        assert!(!main.do_state(&mut report, &mut logger).unwrap());
+        let state = State::load("test", "test_ezcron_rate_limit").unwrap();
+        assert_eq!(state.suppressed_count, 1);
+        assert_eq!(state.reported_at.len(), 1);
+    }
+
+    #[test]
+    // 結果が変わった場合は制限に関わらず通知されるか確認する
+    fn test_ezcron_rate_limit_recovery() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_rate_limit_recovery.toml",
+            "test", "--", "sh", "-c", "exit 1"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_rate_limit_recovery".to_string(),
+                pid_dir: "test_ezcron_rate_limit_recovery".to_string(),
+                state_dir: Some("test_ezcron_rate_limit_recovery".to_string()),
+                ..Default::default()
+            },
+            option: Some(ConfigOption {
+                notify_mode: Some(NotifyMode::Change),
+                report_cooldown: Some(3600),
+                max_reports_per_hour: Some(1),
+                ..ConfigOption::new()
+            }),
+            options: HashMap::new(),
+        };
+        let _test_dir = TestDir::new("test_ezcron_rate_limit_recovery");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_rate_limit_recovery.toml", &test_config);
+        let main = EzCron::new(&matches).unwrap();
+        let mut logger = Logger::new(&main.identifer, &main.log_dir).unwrap();
+        let mut report = main.do_exec(&args, &mut logger).unwrap();
+        assert!(main.do_state(&mut report, &mut logger).unwrap());
+        let mut report = main.do_exec(&["true".to_string()], &mut logger).unwrap();
+        assert!(main.do_state(&mut report, &mut logger).unwrap());
+        assert_eq!(report.previous_outcome, Some(ReportOutcome::Failure));
+        assert_eq!(report.suppressed_count, 0);
+        let mut report = main.do_exec(&["true".to_string()], &mut logger).unwrap();
+        assert!(!main.do_state(&mut report, &mut logger).unwrap());
+        let state = State::load("test", "test_ezcron_rate_limit_recovery").unwrap();
+        assert_eq!(state.suppressed_count, 0);
+        assert_eq!(state.reported_at.len(), 2);
+    }
+
+    #[test]
+    // スキップが続く場合も通知を制限し、前回の結果は変更しないか確認する
+    fn test_ezcron_rate_limit_skip() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_rate_limit_skip.toml",
+            "test", "--", "sh", "-c", "exit 1"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_rate_limit_skip".to_string(),
+                pid_dir: "test_ezcron_rate_limit_skip".to_string(),
+                state_dir: Some("test_ezcron_rate_limit_skip".to_string()),
+                ..Default::default()
+            },
+            option: Some(ConfigOption {
+                max_reports_per_hour: Some(1),
+                ..ConfigOption::new()
+            }),
+            options: HashMap::new(),
+        };
+        let _test_dir = TestDir::new("test_ezcron_rate_limit_skip");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_rate_limit_skip.toml", &test_config);
+        let main = EzCron::new(&matches).unwrap();
+        let mut logger = Logger::new(&main.identifer, &main.log_dir).unwrap();
+        let mut report = main.do_exec(&args, &mut logger).unwrap();
+        assert!(main.do_state(&mut report, &mut logger).unwrap());
+        std::fs::write("test_ezcron_rate_limit_skip/test.pid", "100").unwrap();
+        let mut report = main.do_exec(&args, &mut logger).unwrap();
+        assert_eq!(report.outcome, Some(ReportOutcome::Skipped));
+        // 前回の結果と異なるため通知するが、続くスキップは制限する
+        assert!(main.do_state(&mut report, &mut logger).unwrap());
+        for _ in 0..2 {
+            let mut report = main.do_exec(&args, &mut logger).unwrap();
+            assert!(!main.do_state(&mut report, &mut logger).unwrap());
+            assert_eq!(report.previous_outcome, Some(ReportOutcome::Failure));
+        }
+        let state = State::load("test", "test_ezcron_rate_limit_skip").unwrap();
+        assert_eq!(state.last_outcome, Some(ReportOutcome::Failure));
+        assert_eq!(state.consecutive_failures, 1);
+        assert_eq!(state.suppressed_count, 2);
+        assert!(state.skipped);
+    }
+
+    #[test]
+    // Webhookにレポートが送信されるか確認する
+    fn test_ezcron_webhooks() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_webhooks.toml",
+            "test", "--", "echo", "hello"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        let (url, handle) = crate::webhook::tests::serve(vec![200, 200]);
+        let mut slack = ConfigWebhook::new(&url);
+        slack.preset = Some(Preset::Slack);
+        let mut options = HashMap::new();
+        options.insert("test".to_string(), ConfigOption {
+            webhooks: vec![ConfigWebhook::new(&url), slack],
+            ..ConfigOption::new()
+        });
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_webhooks".to_string(),
+                pid_dir: "test_ezcron_webhooks".to_string(),
+                ..Default::default()
+            },
+            option: None,
+            options,
+        };
+        let _test_dir = TestDir::new("test_ezcron_webhooks");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_webhooks.toml", &test_config);
+        let main = EzCron::new(&matches).unwrap();
+        let mut logger = Logger::new(&main.identifer, &main.log_dir).unwrap();
+        let report = main.do_exec(&args, &mut logger).unwrap();
+        main.do_report(&report, &mut logger).unwrap();
+        let requests = handle.join().unwrap();
+        let json: serde_json::Value = serde_json::from_str(&requests[0].body).unwrap();
+        assert_eq!(json["identifer"], "test");
+        assert_eq!(json["outcome"], "success");
+        let json: serde_json::Value = serde_json::from_str(&requests[1].body).unwrap();
+        assert_eq!(json["attachments"][0]["color"], "#2eb886");
+        assert_eq!(json["attachments"][0]["text"], "```\nhello\n```");
+    }
+
+    #[test]
+    // スキップした場合はwebhookを送信しないか確認する
+    fn test_ezcron_webhooks_skip() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_webhooks_skip.toml",
+            "test", "--", "echo", "hello"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        let (url, handle) = crate::webhook::tests::serve(vec![200]);
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_webhooks_skip".to_string(),
+                pid_dir: "test_ezcron_webhooks_skip".to_string(),
+                ..Default::default()
+            },
+            option: Some(ConfigOption {
+                webhooks: vec![ConfigWebhook::new(&url)],
+                ..ConfigOption::new()
+            }),
+            options: HashMap::new(),
+        };
+        let _test_dir = TestDir::new("test_ezcron_webhooks_skip");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_webhooks_skip.toml", &test_config);
+        let main = EzCron::new(&matches).unwrap();
+        let mut logger = Logger::new(&main.identifer, &main.log_dir).unwrap();
+        std::fs::write("test_ezcron_webhooks_skip/test.pid", "100").unwrap();
+        let report = main.do_exec(&args, &mut logger).unwrap();
+        assert_eq!(report.outcome, Some(ReportOutcome::Skipped));
+        assert!(main.do_report(&report, &mut logger).unwrap().is_empty());
+        std::fs::remove_file("test_ezcron_webhooks_skip/test.pid").unwrap();
+        let report = main.do_exec(&args, &mut logger).unwrap();
+        main.do_report(&report, &mut logger).unwrap();
+        // 受け付けた1件がスキップ後の実行の通知であること
+        let requests = handle.join().unwrap();
+        let json: serde_json::Value = serde_json::from_str(&requests[0].body).unwrap();
+        assert_eq!(json["outcome"], "success");
+    }
+
+    #[test]
+    // メールでレポートが送信されるか確認する
+    fn test_ezcron_mail() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_mail.toml",
+            "test", "--", "echo", "hello"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        let (port, handle) = crate::mail::tests::serve();
+        let mut smtp = ConfigSmtp::new("127.0.0.1", "ezcron@example.com");
+        smtp.port = port;
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_mail".to_string(),
+                pid_dir: "test_ezcron_mail".to_string(),
+                smtp: Some(smtp),
+                ..Default::default()
+            },
+            option: Some(ConfigOption {
+                mail_to: vec!["admin@example.com".to_string()],
+                mail_log: Some(MailLog::Attach),
+                ..ConfigOption::new()
+            }),
+            options: HashMap::new(),
+        };
+        let _test_dir = TestDir::new("test_ezcron_mail");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_mail.toml", &test_config);
+        let main = EzCron::new(&matches).unwrap();
+        let mut logger = Logger::new(&main.identifer, &main.log_dir).unwrap();
+        let report = main.do_exec(&args, &mut logger).unwrap();
+        main.do_report(&report, &mut logger).unwrap();
+        let mail = handle.join().unwrap();
+        assert_eq!(mail.to, vec!["<admin@example.com>"]);
+        assert!(mail.data.contains("Content-Type: multipart/mixed"));
+        assert!(mail.data.contains("Content-Disposition: attachment"));
+    }
+
+    #[test]
+    // スキップした場合はメールを送らないか確認する
+    fn test_ezcron_mail_skip() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_mail_skip.toml",
+            "test", "--", "echo", "hello"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_mail_skip".to_string(),
+                pid_dir: "test_ezcron_mail_skip".to_string(),
+                ..Default::default()
+            },
+            option: Some(ConfigOption {
+                mail_to: vec!["admin@example.com".to_string()],
+                ..ConfigOption::new()
+            }),
+            options: HashMap::new(),
+        };
+        let _test_dir = TestDir::new("test_ezcron_mail_skip");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_mail_skip.toml", &test_config);
+        let main = EzCron::new(&matches).unwrap();
+        let mut logger = Logger::new(&main.identifer, &main.log_dir).unwrap();
+        std::fs::write("test_ezcron_mail_skip/test.pid", "100").unwrap();
+        let report = main.do_exec(&args, &mut logger).unwrap();
+        assert_eq!(report.outcome, Some(ReportOutcome::Skipped));
+        assert!(main.do_report(&report, &mut logger).unwrap().is_empty());
+        std::fs::remove_file("test_ezcron_mail_skip/test.pid").unwrap();
+        let report = main.do_exec(&args, &mut logger).unwrap();
+        let results = main.do_report(&report, &mut logger).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].reporter, "mailto:admin@example.com");
+    }
+
+    #[test]
+    // テンプレートで作成した内容が渡されるか確認する
+    fn test_ezcron_template() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_template.toml",
+            "test", "--", "echo", "hello"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        let (url, handle) = crate::webhook::tests::serve(vec![200]);
+        let mut webhook = ConfigWebhook::new(&url);
+        webhook.template = Some(r#"{"text": "{{ identifer }} {{ outcome }}"}"#.to_string());
+        let mut reporter = ConfigReporter::new("cat > test_ezcron_template/report.txt");
+        reporter.template = Some("{{ identifer }} {{ exitcode }} {{ log_tail[-3] }}".to_string());
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_template".to_string(),
+                pid_dir: "test_ezcron_template".to_string(),
+                ..Default::default()
+            },
+            option: Some(ConfigOption {
+                reports: vec![reporter],
+                webhooks: vec![webhook],
+                ..ConfigOption::new()
+            }),
+            options: HashMap::new(),
+        };
+        let _test_dir = TestDir::new("test_ezcron_template");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_template.toml", &test_config);
+        let main = EzCron::new(&matches).unwrap();
+        let mut logger = Logger::new(&main.identifer, &main.log_dir).unwrap();
+        let report = main.do_exec(&args, &mut logger).unwrap();
+        main.do_report(&report, &mut logger).unwrap();
+        let requests = handle.join().unwrap();
+        assert_eq!(requests[0].body, r#"{"text": "test success"}"#);
+        let rendered = std::fs::read_to_string("test_ezcron_template/report.txt").unwrap();
+        assert_eq!(rendered, "test 0 hello");
+    }
+
+    #[test]
+    // 環境変数でレポートが渡されるか確認する
+    fn test_ezcron_reporter_env() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_reporter_env.toml",
+            "test", "--", "sh", "-c", "exit 3"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        let script = "(cat; echo \"|$EZCRON_IDENTIFIER|$EZCRON_OUTCOME|$EZCRON_EXITCODE\") > test_ezcron_reporter_env/";
+        let mut reporters = Vec::new();
+        for (input, file) in [(None, "both.txt"), (Some(ReporterInput::Json), "json.txt"), (Some(ReporterInput::Env), "env.txt")] {
+            let mut reporter = ConfigReporter::new(&format!("{}{}", script, file));
+            reporter.template = Some("{{ uuid }}".to_string());
+            reporter.input = input;
+            reporters.push(reporter);
+        }
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_reporter_env".to_string(),
+                pid_dir: "test_ezcron_reporter_env".to_string(),
+                ..Default::default()
+            },
+            option: Some(ConfigOption {
+                reports: reporters,
+                ..ConfigOption::new()
+            }),
+            options: HashMap::new(),
+        };
+        let _test_dir = TestDir::new("test_ezcron_reporter_env");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_reporter_env.toml", &test_config);
+        let main = EzCron::new(&matches).unwrap();
+        let mut logger = Logger::new(&main.identifer, &main.log_dir).unwrap();
+        let report = main.do_exec(&args, &mut logger).unwrap();
+        main.do_report(&report, &mut logger).unwrap();
+        let read = |file: &str| std::fs::read_to_string(format!("test_ezcron_reporter_env/{}", file)).unwrap();
+        assert_eq!(read("both.txt"), format!("{}|test|failure|3\n", report.uuid));
+        assert_eq!(read("json.txt"), format!("{}|||\n", report.uuid));
+        assert_eq!(read("env.txt"), "|test|failure|3\n");
+    }
+
+    #[test]
+    // スクリプトの制限時間、終了コード、終了ステータスへの反映を確認する
+    fn test_ezcron_report_result() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_report_result.toml",
+            "test", "--", "echo", "hello"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        let mut slow = ConfigReporter::new("echo slow; sleep 10");
+        slow.timeout = Some(1);
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_report_result".to_string(),
+                pid_dir: "test_ezcron_report_result".to_string(),
+                state_dir: Some("test_ezcron_report_result".to_string()),
+                ..Default::default()
+            },
+            option: Some(ConfigOption {
+                reports: vec!["exit 0".into(), "exit 5".into(), slow],
+                fail_on_report_error: Some(true),
+                ..ConfigOption::new()
+            }),
+            options: HashMap::new(),
+        };
+        let _test_dir = TestDir::new("test_ezcron_report_result");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_report_result.toml", &test_config);
+        let main = EzCron::new(&matches).unwrap();
+        let started = std::time::Instant::now();
+        assert_eq!(main.run(&args).unwrap(), EXIT_REPORT_ERROR);
+        assert!(started.elapsed() < Duration::from_secs(5));
+        let state = State::load("test", "test_ezcron_report_result").unwrap();
+        let results = state.last_reporters;
+        assert_eq!(results.len(), 3);
+        assert!(results[0].success);
+        assert_eq!(results[0].exitcode, Some(0));
+        assert!(!results[1].success);
+        assert_eq!(results[1].exitcode, Some(5));
+        assert!(!results[2].success);
+        assert!(results[2].timed_out);
+        assert!(results[2].duration >= 1.0);
+        // 実行の履歴が記録される
+        let records = history::load("test", "test_ezcron_report_result").unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].report.outcome, Some(ReportOutcome::Success));
+        assert!(records[0].report.usage.is_some());
+        assert_eq!(records[0].reporters.len(), 3);
+    }
+
+    #[test]
+    // 制限時間を過ぎたスクリプトの子孫のプロセスも終了させるか確認する
+    fn test_ezcron_report_timeout_group() {
+        let _test_dir = TestDir::new("test_ezcron_report_timeout_group");
+        let mut logger = Logger::new("test", "test_ezcron_report_timeout_group").unwrap();
+        let reporter = ConfigReporter::new("sh -c 'sleep 5; touch test_ezcron_report_timeout_group/done'; :");
+        let started = Instant::now();
+        let result = execute_report(&reporter, &Report::default(), &[], 1, &mut logger).unwrap();
+        assert!(result.timed_out);
+        assert!(started.elapsed() < Duration::from_secs(4));
+        // 孫のプロセスが残っていれば作成されるファイルが無いこと
+        thread::sleep(Duration::from_secs(6) - started.elapsed());
+        assert!(!Path::new("test_ezcron_report_timeout_group/done").exists());
+    }
+
+    #[test]
+    // スクリプトが同時に実行されるか確認する
+    fn test_ezcron_report_parallel() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_report_parallel.toml",
+            "test", "--", "echo", "hello"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_report_parallel".to_string(),
+                pid_dir: "test_ezcron_report_parallel".to_string(),
+                ..Default::default()
+            },
+            option: Some(ConfigOption {
+                reports: vec!["sleep 1; echo first".into(), "sleep 1; echo second".into(), "sleep 1; echo third".into()],
+                report_mode: Some(ReportMode::Parallel),
+                ..ConfigOption::new()
+            }),
+            options: HashMap::new(),
+        };
+        let _test_dir = TestDir::new("test_ezcron_report_parallel");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_report_parallel.toml", &test_config);
+        let main = EzCron::new(&matches).unwrap();
+        let mut logger = Logger::new(&main.identifer, &main.log_dir).unwrap();
+        let report = main.do_exec(&args, &mut logger).unwrap();
+        let started = std::time::Instant::now();
+        let results = main.do_report(&report, &mut logger).unwrap();
+        assert!(started.elapsed() < Duration::from_millis(2500));
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|result| result.success));
+        // ログは通知ごとにまとめて書き込まれる
+        let log = std::fs::read_to_string(&logger.path).unwrap();
+        let lines: Vec<&str> = log.lines().map(|line| line.split_once('|').unwrap().1).collect();
+        let pos = lines.iter().position(|line| *line == "starting repot! 'sleep 1; echo second'").unwrap();
+        assert_eq!(lines[pos + 2], "second");
+    }
+
+    #[test]
+    // スクリプトの終了を待たずに終了するか確認する
+    fn test_ezcron_report_detached() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_report_detached.toml",
+            "test", "--", "echo", "hello"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_report_detached".to_string(),
+                pid_dir: "test_ezcron_report_detached".to_string(),
+                ..Default::default()
+            },
+            option: Some(ConfigOption {
+                reports: vec!["sleep 1; cat".into()],
+                report_mode: Some(ReportMode::Detached),
+                report_log: Some(ReportLog::Sidecar),
+                ..ConfigOption::new()
+            }),
+            options: HashMap::new(),
+        };
+        let _test_dir = TestDir::new("test_ezcron_report_detached");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_report_detached.toml", &test_config);
+        let main = EzCron::new(&matches).unwrap();
+        let mut logger = Logger::new(&main.identifer, &main.log_dir).unwrap();
+        let report = main.do_exec(&args, &mut logger).unwrap();
+        let started = std::time::Instant::now();
+        let results = main.do_report(&report, &mut logger).unwrap();
+        assert!(started.elapsed() < Duration::from_millis(500));
+        assert!(results[0].detached);
+        assert!(results[0].success);
+        // 出力はサイドカーファイルに書き込まれる
+        let sidecar = logger.path.replace(".log", ".report.log");
+        for _ in 0..50 {
+            if std::fs::read_to_string(&sidecar).is_ok_and(|output| !output.is_empty()) {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        let output = std::fs::read_to_string(&sidecar).unwrap();
+        assert!(output.contains(&format!("\"uuid\":\"{}\"", report.uuid)));
+    }
+
+    #[test]
+    // 失敗した通知がスプールに保存され、再送されるか確認する
+    fn test_ezcron_spool() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_spool.toml",
+            "test", "--", "echo", "hello"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        let (url, handle) = crate::webhook::tests::serve(vec![503, 200]);
+        let mut webhook = ConfigWebhook::new(&url);
+        webhook.retries = Some(0);
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_spool".to_string(),
+                pid_dir: "test_ezcron_spool".to_string(),
+                spool_dir: Some("test_ezcron_spool/spool".to_string()),
+                spool_retry_interval: Some(0),
+                ..Default::default()
+            },
+            option: Some(ConfigOption {
+                webhooks: vec![webhook],
+                ..ConfigOption::new()
+            }),
+            options: HashMap::new(),
+        };
+        let _test_dir = TestDir::new("test_ezcron_spool");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_spool.toml", &test_config);
+        let main = EzCron::new(&matches).unwrap();
+        let mut logger = Logger::new(&main.identifer, &main.log_dir).unwrap();
+        let report = main.do_exec(&args, &mut logger).unwrap();
+        let results = main.do_report(&report, &mut logger).unwrap();
+        assert!(!results[0].success);
+        assert_eq!(spool::list("test_ezcron_spool/spool").unwrap().len(), 1);
+        let conf = config::load(Some("./test_ezcron_spool.toml".to_string())).unwrap();
+        let flushed = spool::flush(&conf).unwrap();
+        assert_eq!(flushed, spool::Flushed { delivered: 1, retrying: 0, gave_up: 0, broken: 0 });
+        assert!(spool::list("test_ezcron_spool/spool").unwrap().is_empty());
+        let requests = handle.join().unwrap();
+        // 再送したレポートが元のレポートと同じか確認する(実数は末尾の桁が変わり得るため比較しない)
+        let first: serde_json::Value = serde_json::from_str(&requests[0].body).unwrap();
+        let second: serde_json::Value = serde_json::from_str(&requests[1].body).unwrap();
+        for key in ["uuid", "identifer", "command", "exitcode", "outcome", "start_at", "end_at", "output_tail"] {
+            assert_eq!(first[key], second[key], "{}", key);
+        }
+        let log = std::fs::read_to_string(&logger.path).unwrap();
+        assert!(log.contains("|retrying spooled report!(1)"));
+    }
+
+    #[test]
+    // 引数の配列で指定したスクリプトがシェルを介さずに実行されるか確認する
+    fn test_ezcron_reporter_argv() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_reporter_argv.toml",
+            "test", "--", "sh", "-c", "exit 3"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        let argv = ["touch", "test_ezcron_reporter_argv/{identifer} {outcome}({exitcode}) {unknown}.txt"];
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_reporter_argv".to_string(),
+                pid_dir: "test_ezcron_reporter_argv".to_string(),
+                ..Default::default()
+            },
+            option: Some(ConfigOption {
+                reports: vec![ConfigReporter::with_argv(argv.iter().map(|arg| arg.to_string()).collect()).unwrap()],
+                ..ConfigOption::new()
+            }),
+            options: HashMap::new(),
+        };
+        let _test_dir = TestDir::new("test_ezcron_reporter_argv");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_reporter_argv.toml", &test_config);
+        let main = EzCron::new(&matches).unwrap();
+        let mut logger = Logger::new(&main.identifer, &main.log_dir).unwrap();
+        let report = main.do_exec(&args, &mut logger).unwrap();
+        let results = main.do_report(&report, &mut logger).unwrap();
+        assert!(results[0].success);
+        assert!(Path::new("test_ezcron_reporter_argv/test failure(3) {unknown}.txt").is_file());
+    }
+
+    #[test]
+    // 出力の先頭、末尾の行と、行数、バイト数が記録されるか確認する
+    fn test_ezcron_output() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_output.toml",
+            "test", "--", "sh", "-c", "for i in 1 2 3 4 5; do echo line$i; done"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        let mut options = HashMap::new();
+        options.insert("test".to_string(), ConfigOption {
+            output_tail_lines: Some(2),
+            ..ConfigOption::new()
+        });
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_output".to_string(),
+                pid_dir: "test_ezcron_output".to_string(),
+                ..Default::default()
+            },
+            option: Some(ConfigOption {
+                output_tail_lines: Some(10),
+                output_head_lines: Some(1),
+                ..ConfigOption::new()
+            }),
+            options,
+        };
+        let _test_dir = TestDir::new("test_ezcron_output");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_output.toml", &test_config);
+        let main = EzCron::new(&matches).unwrap();
+        let mut logger = Logger::new(&main.identifer, &main.log_dir).unwrap();
+        let report = main.do_exec(&args, &mut logger).unwrap();
+        assert_eq!(report.output_head, vec!["line1"]);
+        assert_eq!(report.output_tail, vec!["line4", "line5"]);
+        assert_eq!(report.output_lines, 5);
+        assert_eq!(report.output_bytes, 30);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["output_tail"][1], "line5");
+        assert_eq!(json["output_bytes"], 30);
+    }
+
+    #[test]
+    // 出力のバイト数を変換前の内容で数えるか確認する
+    fn test_ezcron_output_bytes() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_output_bytes.toml",
+            "test", "--", "sh", "-c", "printf 'aaa\\r\\nb\\377b\\nccc'"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_output_bytes".to_string(),
+                pid_dir: "test_ezcron_output_bytes".to_string(),
+                ..Default::default()
+            },
+            option: None,
+            options: HashMap::new(),
+        };
+        let _test_dir = TestDir::new("test_ezcron_output_bytes");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_output_bytes.toml", &test_config);
+        let main = EzCron::new(&matches).unwrap();
+        let mut logger = Logger::new(&main.identifer, &main.log_dir).unwrap();
+        let report = main.do_exec(&args, &mut logger).unwrap();
+        assert_eq!(report.output_tail, vec!["aaa", "b\u{fffd}b", "ccc"]);
+        assert_eq!(report.output_lines, 3);
+        // CRLF、UTF-8で無いバイト、末尾に改行の無い行をそのまま数える
+        assert_eq!(report.output_bytes, 5 + 4 + 3);
+    }
+
+    #[test]
+    // 他のプロセスから停止した操作がレポートとログに記録されるか確認する
+    fn test_ezcron_kill() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_kill.toml",
+            "test", "--", "sh", "-c", "sleep 30"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_kill".to_string(),
+                pid_dir: "test_ezcron_kill".to_string(),
+                ..Default::default()
+            },
+            option: None,
+            options: HashMap::new(),
+        };
+        let _test_dir = TestDir::new("test_ezcron_kill");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_kill.toml", &test_config);
+        let main = EzCron::new(&matches).unwrap();
+        let mut logger = Logger::new(&main.identifer, &main.log_dir).unwrap();
+        let killer = thread::spawn(|| {
+            for _ in 0..50 {
+                if let Ok(status) = control::find("test", "test_ezcron_kill") {
+                    control::pause(&status, "test_ezcron_kill").unwrap();
+                    control::resume(&status, "test_ezcron_kill").unwrap();
+                    return control::kill(&status, "test_ezcron_kill", 5).unwrap();
+                }
+                thread::sleep(Duration::from_millis(100));
+            }
+            panic!("job is not found");
+        });
+        let report = main.do_exec(&args, &mut logger).unwrap();
+        assert!(!killer.join().unwrap());
+        let actions = report.actions.iter().map(|action| action.action.as_str()).collect::<Vec<&str>>();
+        assert_eq!(actions, vec!["pause(SIGSTOP)", "resume(SIGCONT)", "kill(SIGTERM)"]);
+        assert_eq!(report.outcome, Some(ReportOutcome::Failure));
+        let log = std::fs::read_to_string(&logger.path).unwrap();
+        assert!(log.contains("|kill(SIGTERM) by ezcron"));
+        assert!(!Path::new("test_ezcron_kill").join(format!("test-{}.actions.jsonl", report.uuid)).exists());
+    }
+
+    #[test]
+    // SIGTERMを無視する子孫のプロセスも停止するか確認する
+    fn test_ezcron_kill_group() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_kill_group.toml",
+            "test", "--", "sh", "-c", "sh -c 'trap \"\" TERM; sleep 30' & wait"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_kill_group".to_string(),
+                pid_dir: "test_ezcron_kill_group".to_string(),
+                ..Default::default()
+            },
+            option: None,
+            options: HashMap::new(),
+        };
+        let _test_dir = TestDir::new("test_ezcron_kill_group");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_kill_group.toml", &test_config);
+        let main = EzCron::new(&matches).unwrap();
+        let mut logger = Logger::new(&main.identifer, &main.log_dir).unwrap();
+        let killer = thread::spawn(|| {
+            for _ in 0..50 {
+                if let Ok(status) = control::find("test", "test_ezcron_kill_group") {
+                    // 子孫のプロセスが起動するまで待つ
+                    thread::sleep(Duration::from_millis(500));
+                    return control::kill(&status, "test_ezcron_kill_group", 1).unwrap();
+                }
+                thread::sleep(Duration::from_millis(100));
+            }
+            panic!("job is not found");
+        });
+        let started = Instant::now();
+        let report = main.do_exec(&args, &mut logger).unwrap();
+        assert!(killer.join().unwrap());
+        assert!(started.elapsed() < Duration::from_secs(10));
+        let actions = report.actions.iter().map(|action| action.action.as_str()).collect::<Vec<&str>>();
+        assert_eq!(actions, vec!["kill(SIGTERM)", "kill(SIGKILL)"]);
+    }
+
+    #[test]
+    // 実行せずに設定の反映結果と環境変数の設定元を表示するか確認する
+    fn test_ezcron_explain() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_explain.toml",
+            "-e", "TEST_EXPLAIN=${TEST_EXPLAIN}-cli",
+            "--dry-run", "test"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        assert!(args.is_empty());
+        let mut env = RegistOrderMap::new();
+        env.insert("TEST_EXPLAIN".to_string(), "base".to_string());
+        let mut job_env = RegistOrderMap::new();
+        job_env.insert("TEST_EXPLAIN".to_string(), "${TEST_EXPLAIN}-job".to_string());
+        let mut options = HashMap::new();
+        options.insert("test".to_string(), ConfigOption {
+            reports: vec!["report01.sh".into()],
+            env: job_env,
+            ..ConfigOption::new()
+        });
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_explain".to_string(),
+                pid_dir: "test_ezcron_explain".to_string(),
+                ..Default::default()
+            },
+            option: Some(ConfigOption {
+                reports: vec!["report00.sh".into()],
+                env,
+                ..ConfigOption::new()
+            }),
+            options,
+        };
+        let _test_config_file = TestConfigFile::new("./test_ezcron_explain.toml", &test_config);
+        let main = EzCron::new(&matches).unwrap();
+        let sources = main.env.iter()
+            .map(|env| (env.value.as_str(), env.source.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(sources, vec![("base", "[option]"), ("base-job", "[options.test]"), ("base-job-cli", "--env")]);
+        assert_eq!(main.env[1].previous, Some("base".to_string()));
+        let text = main.explain(&args);
+        assert!(text.contains("reports:              report00.sh\n                      report01.sh\n"));
+        assert!(text.contains("lock:                 test_ezcron_explain/test.pid\n"));
+        assert!(text.contains("TEST_EXPLAIN=base-job-cli (--env, was 'base-job')"));
+        assert!(!Path::new("test_ezcron_explain").exists());
+    }
+
+    #[test]
+    // 設定ファイルのコマンドを実行するか確認する
+    fn test_ezcron_command() {
+        let mut args = ["program",
+            "-c", "./test_ezcron_command.toml",
+            "test"
+        ].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        let mut options = HashMap::new();
+        options.insert("test".to_string(), ConfigOption {
+            command: Some(ConfigCommand::Shell("echo hello | tr a-z A-Z".to_string())),
+            ..ConfigOption::new()
+        });
+        let test_config = Config {
+            ezcron: ConfigEzCron {
+                log_dir: "test_ezcron_command".to_string(),
+                pid_dir: "test_ezcron_command".to_string(),
+                state_dir: Some("test_ezcron_command".to_string()),
+                ..Default::default()
+            },
+            option: None,
+            options,
+        };
+        let _test_dir = TestDir::new("test_ezcron_command");
+        let _test_config_file = TestConfigFile::new("./test_ezcron_command.toml", &test_config);
+        let main = EzCron::new(&matches).unwrap();
+        assert_eq!(main.run(&args).unwrap(), 0);
+        let records = history::load("test", "test_ezcron_command").unwrap();
+        assert_eq!(records[0].report.args, vec!["sh", "-c", "echo hello | tr a-z A-Z"]);
+        assert_eq!(records[0].report.output_tail, vec!["HELLO"]);
+
+        // 引数を指定した場合は引数のコマンドを実行する
+        let args = vec!["echo".to_string(), "world".to_string()];
+        assert_eq!(main.run(&args).unwrap(), 0);
+        let records = history::load("test", "test_ezcron_command").unwrap();
+        assert_eq!(records[1].report.output_tail, vec!["world"]);
+    }
 }
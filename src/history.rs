@@ -0,0 +1,341 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::report::{Report, ReportOutcome, ReporterResult};
+
+pub const DEFAULT_MAX_LINES: usize = 10000;
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RunRecord {
+    #[serde(flatten)]
+    pub report: Report,
+    #[serde(default)]
+    pub reporters: Vec<ReporterResult>,
+}
+
+fn history_dir(state_dir: &str) -> PathBuf {
+    Path::new(state_dir).join("history")
+}
+
+pub fn path(identifer: &str, state_dir: &str) -> PathBuf {
+    history_dir(state_dir)
+        .join(format!("{}.jsonl", identifer))
+}
+
+pub fn append(state_dir: &str, record: &RunRecord, max_lines: usize) -> Result<(), Box<dyn std::error::Error>> {
+    // 1回の実行を1行のJSONとして追記する
+    fs::create_dir_all(history_dir(state_dir))?;
+    let path = path(&record.report.identifer, state_dir);
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)?;
+    let line = format!("{}\n", serde_json::to_string(record)?);
+    file.write_all(line.as_bytes())?;
+    drop(file);
+
+    // 行数の上限を超えた場合は古い行を削除する(0は無制限)
+    if max_lines > 0 {
+        truncate(&path, max_lines)?;
+    }
+    Ok(())
+}
+
+fn truncate(path: &Path, max_lines: usize) -> Result<(), Box<dyn std::error::Error>> {
+    let content = fs::read(path)?;
+    let lines = content.iter().filter(|c| **c == b'\n').count();
+    if lines <= max_lines {
+        return Ok(());
+    }
+    // 新しい方からmax_lines行を残し、一時ファイルに書き込んでから置き換える
+    let start = content.split_inclusive(|c| *c == b'\n')
+        .take(lines - max_lines)
+        .map(|line| line.len())
+        .sum::<usize>();
+    let tmp_path = path.with_extension("jsonl.tmp");
+    fs::write(&tmp_path, &content[start..])?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+pub fn load(identifer: &str, state_dir: &str) -> Result<Vec<RunRecord>, Box<dyn std::error::Error>> {
+    // 履歴が無ければ空とする
+    let path = path(identifer, state_dir);
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    // 書き込み途中などで壊れた行は読み飛ばす
+    let br = BufReader::new(File::open(path)?);
+    Ok(br.lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect())
+}
+
+pub fn identifers(state_dir: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    // 履歴のあるジョブの識別子を得る
+    let dir = history_dir(state_dir);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut identifers = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+        .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+        .collect::<Vec<String>>();
+    identifers.sort();
+    Ok(identifers)
+}
+
+impl RunRecord {
+    pub fn duration(&self) -> Option<f64> {
+        self.report.end_at
+            .map(|end_at| (end_at - self.report.start_at).num_milliseconds() as f64 / 1000.0)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RunSummary {
+    pub uuid: String,
+    pub start_at: DateTime<Local>,
+    pub outcome: Option<ReportOutcome>,
+    pub exitcode: u32,
+    pub duration: Option<f64>,
+    pub log: String,
+}
+
+impl From<&RunRecord> for RunSummary {
+    fn from(record: &RunRecord) -> Self {
+        Self {
+            uuid: record.report.uuid.to_string(),
+            start_at: record.report.start_at,
+            outcome: record.report.outcome,
+            exitcode: record.report.exitcode,
+            duration: record.duration(),
+            log: record.report.log.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Stats {
+    pub identifer: String,
+    pub runs: usize,
+    pub successes: usize,
+    pub failures: usize,
+    pub skipped: usize,
+    pub success_rate: Option<f64>,
+    pub p50: Option<f64>,
+    pub p95: Option<f64>,
+    pub max: Option<f64>,
+    pub last_failure: Option<RunSummary>,
+    pub recent: Vec<RunSummary>,
+}
+
+fn percentile(sorted: &[f64], percent: f64) -> Option<f64> {
+    // 最近傍順位法で求める
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (percent / 100.0 * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+pub fn stats(identifer: &str, records: &[RunRecord], recent: usize) -> Stats {
+    // スキップした実行は成功率、所要時間の対象外とする
+    let executed: Vec<&RunRecord> = records.iter()
+        .filter(|record| record.report.outcome != Some(ReportOutcome::Skipped))
+        .collect();
+    let failures = executed.iter()
+        .filter(|record| record.report.outcome.is_some_and(|outcome| outcome.is_failure()))
+        .count();
+    let mut durations: Vec<f64> = executed.iter()
+        .filter_map(|record| record.duration())
+        .collect();
+    durations.sort_by(|a, b| a.total_cmp(b));
+    Stats {
+        identifer: identifer.to_string(),
+        runs: executed.len(),
+        successes: executed.len() - failures,
+        failures,
+        skipped: records.len() - executed.len(),
+        success_rate: (!executed.is_empty())
+            .then(|| (executed.len() - failures) as f64 / executed.len() as f64 * 100.0),
+        p50: percentile(&durations, 50.0),
+        p95: percentile(&durations, 95.0),
+        max: durations.last().copied(),
+        last_failure: executed.iter()
+            .rev()
+            .find(|record| record.report.outcome.is_some_and(|outcome| outcome.is_failure()))
+            .map(|record| RunSummary::from(*record)),
+        recent: records.iter()
+            .rev()
+            .take(recent)
+            .map(RunSummary::from)
+            .collect(),
+    }
+}
+
+pub fn parse_since(value: &str, now: DateTime<Local>) -> Result<DateTime<Local>, Box<dyn std::error::Error>> {
+    // "7d"、"12h"、"30m"のような相対指定
+    if let Some(unit) = value.chars().last().filter(|unit| "dhm".contains(*unit)) {
+        if let Ok(number) = value[..value.len() - 1].parse::<i64>() {
+            // 範囲外の期間はエラーとする
+            let millis = match unit {
+                'd' => 86_400_000,
+                'h' => 3_600_000,
+                _ => 60_000,
+            };
+            return number.checked_mul(millis)
+                .and_then(|millis| now.checked_sub_signed(Duration::milliseconds(millis)))
+                .ok_or(format!("invalid since '{}'", value).into());
+        }
+    }
+    // "2023-12-11"のような日付
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        let datetime = date.and_hms_opt(0, 0, 0).unwrap();
+        return Local.from_local_datetime(&datetime).earliest()
+            .ok_or(format!("invalid date '{}'", value).into());
+    }
+    // RFC3339の日時
+    Ok(DateTime::parse_from_rfc3339(value)
+        .map_err(|_| format!("invalid since '{}'", value))?
+        .with_timezone(&Local))
+}
+
+fn format_duration(duration: Option<f64>) -> String {
+    duration.map_or("-".to_string(), |duration| format!("{:.1}s", duration))
+}
+
+pub fn format(stats: &Stats) -> String {
+    let success_rate = stats.success_rate.map_or("-".to_string(), |rate| format!("{:.1}%", rate));
+    let mut text = format!("{}: runs {}, success {}, failures {}, skipped {}, p50 {}, p95 {}, max {}\n",
+        stats.identifer, stats.runs, success_rate, stats.failures, stats.skipped,
+        format_duration(stats.p50), format_duration(stats.p95), format_duration(stats.max));
+    if let Some(failure) = &stats.last_failure {
+        text.push_str(&format!("  last failure: {} {} exit({}) {}\n",
+            failure.start_at.format("%Y-%m-%d %H:%M:%S"),
+            failure.outcome.map_or("running", |outcome| outcome.as_str()),
+            failure.exitcode,
+            failure.log));
+    }
+    for run in &stats.recent {
+        text.push_str(&format!("  {} {:<8} exit({}) {:>8} {}\n",
+            run.start_at.format("%Y-%m-%d %H:%M:%S"),
+            run.outcome.map_or("running", |outcome| outcome.as_str()),
+            run.exitcode,
+            format_duration(run.duration),
+            run.uuid));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use chrono::{DateTime, Duration, Local, TimeZone};
+    use crate::history::{self, RunRecord};
+    use crate::report::{Report, ReportOutcome, ReporterResult};
+
+    #[test]
+    fn test_history() {
+        const STATE_DIR: &str = "test_history";
+        for (identifer, outcome) in [("job01", ReportOutcome::Success), ("job01", ReportOutcome::Failure), ("job02", ReportOutcome::Success)] {
+            let record = RunRecord {
+                report: Report {
+                    identifer: identifer.to_string(),
+                    outcome: Some(outcome),
+                    ..Default::default()
+                },
+                reporters: vec![ReporterResult::new("report.sh")],
+            };
+            history::append(STATE_DIR, &record, history::DEFAULT_MAX_LINES).unwrap();
+        }
+        // 壊れた行は読み飛ばす
+        let mut file = std::fs::OpenOptions::new().append(true).open("test_history/history/job01.jsonl").unwrap();
+        file.write_all(b"{\"identifer\":").unwrap();
+        let records = history::load("job01", STATE_DIR);
+        let identifers = history::identifers(STATE_DIR);
+        let empty = history::load("job03", STATE_DIR);
+        std::fs::remove_dir_all(STATE_DIR).unwrap();
+        let records = records.unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].report.outcome, Some(ReportOutcome::Success));
+        assert_eq!(records[1].report.outcome, Some(ReportOutcome::Failure));
+        assert_eq!(records[1].reporters[0].reporter, "report.sh");
+        assert_eq!(identifers.unwrap(), vec!["job01", "job02"]);
+        assert!(empty.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_history_max_lines() {
+        const STATE_DIR: &str = "test_history_max_lines";
+        for exitcode in 1..=5 {
+            let record = RunRecord {
+                report: Report {
+                    identifer: "job01".to_string(),
+                    exitcode,
+                    ..Default::default()
+                },
+                reporters: Vec::new(),
+            };
+            history::append(STATE_DIR, &record, 3).unwrap();
+        }
+        let records = history::load("job01", STATE_DIR);
+        std::fs::remove_dir_all(STATE_DIR).unwrap();
+        // 古い行から削除される
+        let exitcodes = records.unwrap().iter().map(|record| record.report.exitcode).collect::<Vec<u32>>();
+        assert_eq!(exitcodes, vec![3, 4, 5]);
+    }
+
+    #[test]
+    fn test_history_stats() {
+        let start_at = Local.with_ymd_and_hms(2023, 12, 11, 0, 0, 0).unwrap();
+        let record = |outcome, seconds| RunRecord {
+            report: Report {
+                identifer: "job01".to_string(),
+                outcome: Some(outcome),
+                start_at,
+                end_at: Some(start_at + Duration::seconds(seconds)),
+                ..Default::default()
+            },
+            reporters: Vec::new(),
+        };
+        let mut records: Vec<RunRecord> = (1..=18).map(|seconds| record(ReportOutcome::Success, seconds)).collect();
+        records.push(record(ReportOutcome::Failure, 19));
+        records.push(record(ReportOutcome::Timeout, 20));
+        records.push(record(ReportOutcome::Skipped, 0));
+        let stats = history::stats("job01", &records, 3);
+        assert_eq!(stats.runs, 20);
+        assert_eq!(stats.successes, 18);
+        assert_eq!(stats.failures, 2);
+        assert_eq!(stats.skipped, 1);
+        assert_eq!(stats.success_rate, Some(90.0));
+        assert_eq!(stats.p50, Some(10.0));
+        assert_eq!(stats.p95, Some(19.0));
+        assert_eq!(stats.max, Some(20.0));
+        assert_eq!(stats.last_failure.as_ref().unwrap().outcome, Some(ReportOutcome::Timeout));
+        assert_eq!(stats.recent.len(), 3);
+        assert_eq!(stats.recent[0].outcome, Some(ReportOutcome::Skipped));
+        assert!(history::format(&stats).starts_with("job01: runs 20, success 90.0%, failures 2, skipped 1, p50 10.0s, p95 19.0s, max 20.0s\n"));
+        let stats = history::stats("job02", &[], 3);
+        assert_eq!(stats.success_rate, None);
+        assert_eq!(stats.p50, None);
+    }
+
+    #[test]
+    fn test_history_parse_since() {
+        let now = Local.with_ymd_and_hms(2023, 12, 11, 12, 0, 0).unwrap();
+        assert_eq!(history::parse_since("7d", now).unwrap(), now - Duration::days(7));
+        assert_eq!(history::parse_since("12h", now).unwrap(), now - Duration::hours(12));
+        assert_eq!(history::parse_since("30m", now).unwrap(), now - Duration::minutes(30));
+        assert_eq!(history::parse_since("2023-12-10", now).unwrap(), Local.with_ymd_and_hms(2023, 12, 10, 0, 0, 0).unwrap());
+        assert_eq!(history::parse_since("2023-12-10T00:00:00Z", now).unwrap(), DateTime::parse_from_rfc3339("2023-12-10T00:00:00Z").unwrap());
+        assert!(history::parse_since("yesterday", now).is_err());
+        assert!(history::parse_since("99999999999999d", now).is_err());
+        assert!(history::parse_since("-99999999999999d", now).is_err());
+    }
+}
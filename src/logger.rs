@@ -1,31 +1,98 @@
-use std::fs::File;
-use std::io::{Write, BufWriter};
-use std::path::Path;
-use chrono::Local;
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write, BufWriter};
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Local};
 
 pub struct Logger {
     pub path: String,
-    bw: BufWriter<File>,
+    pub lines: usize,
+    bw: Option<BufWriter<File>>,
+    buffer: Vec<String>,
+}
+
+pub fn path(identifer: &str, log_dir: &str, now: DateTime<Local>) -> PathBuf {
+    Path::new(log_dir)
+        .join(format!("{}-{}.log", now.format("%Y%m%d-%H%M%S"), identifer))
 }
 
 impl Logger {
     pub fn new(identifer: &str, log_dir: &str) -> std::io::Result<Self> {
-        let log_path = Path::new(log_dir)
-            .join(format!("{}-{}.log", Local::now().format("%Y%m%d-%H%M%S"), identifer));
-        let bw = File::create(log_path.clone())
-            .map(|fs| BufWriter::new(fs))?;
+        let log_path = path(identifer, log_dir, Local::now());
+        // 他のプロセスからの追記を上書きしないように追記モードで開く
+        let bw = OpenOptions::new().create(true).append(true).open(log_path.clone())
+            .map(BufWriter::new)?;
         Ok(Self {
             path: log_path.to_string_lossy().into_owned(),
-            bw: bw,
+            lines: 0,
+            bw: Some(bw),
+            buffer: Vec::new(),
+        })
+    }
+    pub fn open(path: &str) -> std::io::Result<Self> {
+        // 既存のログファイルに追記する
+        let bw = OpenOptions::new().create(true).append(true).open(path)
+            .map(BufWriter::new)?;
+        Ok(Self {
+            path: path.to_string(),
+            lines: 0,
+            bw: Some(bw),
+            buffer: Vec::new(),
         })
     }
+    pub fn buffered(&self) -> Self {
+        // ファイルに書き込まずに行を溜めておくロガーを作成する
+        Self {
+            path: self.path.clone(),
+            lines: self.lines,
+            bw: None,
+            buffer: Vec::new(),
+        }
+    }
     pub fn write(&mut self, line: &str) -> std::io::Result<()> {
         let line = format!("{}|{}\n",
             Local::now().format("%Y-%m-%dT%H:%M:%S"),
             line
         );
-        self.bw.write(line.as_bytes())?;
-        self.bw.flush()
+        self.lines += 1;
+        match self.bw.as_mut() {
+            Some(bw) => {
+                bw.write_all(line.as_bytes())?;
+                bw.flush()
+            },
+            None => {
+                self.buffer.push(line);
+                Ok(())
+            },
+        }
+    }
+    pub fn append(&mut self, other: Logger) -> std::io::Result<()> {
+        // 溜めておいた行をまとめて書き込む
+        for line in other.buffer {
+            self.lines += 1;
+            match self.bw.as_mut() {
+                Some(bw) => bw.write_all(line.as_bytes())?,
+                None => self.buffer.push(line),
+            }
+        }
+        match self.bw.as_mut() {
+            Some(bw) => bw.flush(),
+            None => Ok(()),
+        }
     }
 }
 
+pub fn tail(path: &str, lines: usize) -> std::io::Result<Vec<String>> {
+    // ログファイルの末尾の行を得る
+    let br = BufReader::new(File::open(path)?);
+    let mut tail = VecDeque::with_capacity(lines);
+    for line in br.lines().map_while(Result::ok) {
+        if tail.len() >= lines {
+            tail.pop_front();
+        }
+        if lines > 0 {
+            tail.push_back(line);
+        }
+    }
+    Ok(tail.into())
+}
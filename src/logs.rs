@@ -0,0 +1,172 @@
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use serde::Serialize;
+
+use crate::history;
+use crate::report::ReportOutcome;
+use crate::status;
+
+const FOLLOW_INTERVAL: u64 = 500;
+
+#[derive(Debug, Serialize)]
+pub struct LogEntry {
+    pub path: String,
+    pub start_at: Option<DateTime<Local>>,
+    pub uuid: Option<String>,
+    pub outcome: Option<ReportOutcome>,
+    pub running: bool,
+}
+
+fn start_at(name: &str, identifer: &str) -> Option<DateTime<Local>> {
+    // "YYYYmmdd-HHMMSS-識別子.log"から開始日時を得る
+    let timestamp = name.strip_suffix(&format!("-{}.log", identifer))?;
+    let datetime = NaiveDateTime::parse_from_str(timestamp, "%Y%m%d-%H%M%S").ok()?;
+    Local.from_local_datetime(&datetime).earliest()
+}
+
+pub fn list(identifer: &str, log_dir: &str, state_dir: &str, pid_dir: &str) -> Result<Vec<LogEntry>, Box<dyn std::error::Error>> {
+    if !Path::new(log_dir).is_dir() {
+        return Ok(Vec::new());
+    }
+
+    // ログファイルを開始日時の順に並べる
+    let mut entries = fs::read_dir(log_dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter_map(|path| {
+            let start_at = start_at(&path.file_name()?.to_string_lossy(), identifer)?;
+            Some(LogEntry {
+                path: path.to_string_lossy().into_owned(),
+                start_at: Some(start_at),
+                uuid: None,
+                outcome: None,
+                running: false,
+            })
+        })
+        .collect::<Vec<LogEntry>>();
+    entries.sort_by(|a, b| (a.start_at, &a.path).cmp(&(b.start_at, &b.path)));
+
+    // 実行履歴、実行中の情報からUUIDと結果を得る
+    let records = history::load(identifer, state_dir)?;
+    let running = status::list(pid_dir, Local::now())?;
+    for entry in entries.iter_mut() {
+        let same_file = |log: &str| Path::new(log).file_name() == Path::new(&entry.path).file_name();
+        if let Some(record) = records.iter().rev().find(|record| same_file(&record.report.log)) {
+            entry.uuid = Some(record.report.uuid.to_string());
+            entry.outcome = record.report.outcome;
+        }
+        if let Some(status) = running.iter().find(|status| status.alive && status.log.as_deref().is_some_and(same_file)) {
+            entry.uuid = status.uuid.clone();
+            entry.running = true;
+        }
+    }
+    Ok(entries)
+}
+
+pub fn find(uuid: &str, state_dir: &str, pid_dir: &str) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    // 実行中の情報、実行履歴からUUIDのログファイルを探す
+    let running = status::list(pid_dir, Local::now())?;
+    if let Some(status) = running.iter().find(|status| status.uuid.as_deref() == Some(uuid)) {
+        return Ok(status.log.clone());
+    }
+    for identifer in history::identifers(state_dir)? {
+        let records = history::load(&identifer, state_dir)?;
+        if let Some(record) = records.iter().find(|record| record.report.uuid.to_string() == uuid) {
+            return Ok(Some(record.report.log.clone()));
+        }
+    }
+    Ok(None)
+}
+
+pub fn is_running(path: &str, pid_dir: &str) -> bool {
+    status::list(pid_dir, Local::now())
+        .map(|statuses| statuses.iter().any(|status| status.alive && status.log.as_deref() == Some(path)))
+        .unwrap_or(false)
+}
+
+pub fn follow<W: Write>(path: &str, out: &mut W, mut running: impl FnMut() -> bool) -> std::io::Result<()> {
+    // 実行中の間は追記された内容を出力し続ける
+    let mut file = File::open(path)?;
+    let mut buf = Vec::new();
+    loop {
+        let alive = running();
+        buf.clear();
+        file.read_to_end(&mut buf)?;
+        out.write_all(&buf)?;
+        out.flush()?;
+        if !alive {
+            return Ok(());
+        }
+        thread::sleep(Duration::from_millis(FOLLOW_INTERVAL));
+        // ログファイルが切り詰められた場合は先頭から読み直す
+        if file.metadata()?.len() < file.stream_position()? {
+            file.seek(SeekFrom::Start(0))?;
+        }
+    }
+}
+
+pub fn format(entries: &[LogEntry]) -> String {
+    let mut text = String::new();
+    for (index, entry) in entries.iter().rev().enumerate() {
+        let outcome = if entry.running {
+            "running"
+        } else {
+            entry.outcome.map_or("-", |outcome| outcome.as_str())
+        };
+        text.push_str(&format!("{:>3} {} {:<8} {:<36} {}\n",
+            index + 1,
+            entry.start_at.map_or("-".to_string(), |start_at| start_at.format("%Y-%m-%d %H:%M:%S").to_string()),
+            outcome,
+            entry.uuid.as_deref().unwrap_or("-"),
+            entry.path));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use chrono::{Local, TimeZone};
+    use crate::history::{self, RunRecord};
+    use crate::logs;
+    use crate::report::{Report, ReportOutcome};
+
+    #[test]
+    fn test_logs_list() {
+        const DIR: &str = "test_logs_list";
+        fs::create_dir_all(DIR).unwrap();
+        for name in ["20231211-005418-job01.log", "20231212-005418-job01.log", "20231212-005418-job01.report.log", "20231212-005418-job02.log", "job01.log"] {
+            fs::write(format!("{}/{}", DIR, name), "line\n").unwrap();
+        }
+        let report = Report {
+            identifer: "job01".to_string(),
+            outcome: Some(ReportOutcome::Failure),
+            log: format!("{}/20231211-005418-job01.log", DIR),
+            ..Default::default()
+        };
+        history::append(DIR, &RunRecord { report: report.clone(), reporters: Vec::new() }, history::DEFAULT_MAX_LINES).unwrap();
+        let entries = logs::list("job01", DIR, DIR, DIR);
+        let found = logs::find(&report.uuid.to_string(), DIR, DIR);
+        let not_found = logs::find("unknown", DIR, DIR);
+        let mut out = Vec::new();
+        let followed = logs::follow(&format!("{}/20231212-005418-job02.log", DIR), &mut out, || false);
+        fs::remove_dir_all(DIR).unwrap();
+        let entries = entries.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].start_at, Some(Local.with_ymd_and_hms(2023, 12, 11, 0, 54, 18).unwrap()));
+        assert_eq!(entries[0].uuid, Some(report.uuid.to_string()));
+        assert_eq!(entries[0].outcome, Some(ReportOutcome::Failure));
+        assert_eq!(entries[1].path, format!("{}/20231212-005418-job01.log", DIR));
+        assert_eq!(entries[1].uuid, None);
+        let text = logs::format(&entries);
+        assert!(text.starts_with("  1 2023-12-12 00:54:18 -"));
+        assert!(text.contains("  2 2023-12-11 00:54:18 failure"));
+        assert_eq!(found.unwrap(), Some(report.log));
+        assert_eq!(not_found.unwrap(), None);
+        assert!(followed.is_ok());
+        assert_eq!(out, b"line\n");
+    }
+}
@@ -0,0 +1,213 @@
+use std::path::Path;
+use std::time::Duration;
+use lettre::{Message, SmtpTransport, Transport};
+use lettre::message::{Attachment, MultiPart, SinglePart};
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+
+use crate::config::{ConfigSmtp, MailLog};
+use crate::logger::{self, Logger};
+use crate::report::Report;
+
+const DEFAULT_TIMEOUT: u64 = 30;
+pub const DEFAULT_LOG_LINES: usize = 50;
+
+pub fn subject(report: &Report) -> String {
+    let outcome = report.outcome.map_or("running", |outcome| outcome.as_str());
+    format!("[ezcron] {} {} on {}", report.identifer, outcome, report.hostname)
+}
+
+pub fn body(report: &Report, log_tail: Option<&[String]>) -> String {
+    let outcome = report.outcome.map_or("running", |outcome| outcome.as_str());
+    let end_at = report.end_at.map_or("".to_string(), |end_at| end_at.to_rfc3339());
+    let mut body = format!("process {} {}.
+
+host     : {}
+command  : {}
+exit code: {}
+outcome  : {}
+message  : {}
+log      : {}
+start    : {}
+end      : {}
+",
+        report.identifer, outcome,
+        report.hostname,
+        report.command,
+        report.exitcode,
+        outcome,
+        report.result,
+        report.log,
+        report.start_at.to_rfc3339(),
+        end_at,
+    );
+    if let Some(log_tail) = log_tail {
+        body.push_str("\n--------\n");
+        for line in log_tail {
+            body.push_str(line);
+            body.push('\n');
+        }
+    }
+    body
+}
+
+pub fn send(smtp: &ConfigSmtp, to: &[String], mail_log: MailLog, mail_log_lines: usize, report: &Report, logger: &mut Logger) -> Result<(), Box<dyn std::error::Error>> {
+    // ログファイルの末尾を得る
+    let log_tail = match mail_log {
+        MailLog::None => None,
+        _ => Some(logger::tail(&report.log, mail_log_lines)?),
+    };
+
+    // メールの作成
+    let mut builder = Message::builder()
+        .from(smtp.from.parse()?)
+        .subject(subject(report));
+    for to in to {
+        builder = builder.to(to.parse()?);
+    }
+    let message = match (mail_log, log_tail) {
+        (MailLog::Attach, Some(log_tail)) => {
+            let filename = Path::new(&report.log).file_name()
+                .map_or("ezcron.log".to_string(), |name| name.to_string_lossy().into_owned());
+            let attachment = Attachment::new(filename)
+                .body(log_tail.join("\n"), ContentType::TEXT_PLAIN);
+            builder.multipart(MultiPart::mixed()
+                .singlepart(SinglePart::plain(body(report, None)))
+                .singlepart(attachment))?
+        },
+        (_, log_tail) => {
+            builder
+                .header(ContentType::TEXT_PLAIN)
+                .body(body(report, log_tail.as_deref()))?
+        },
+    };
+
+    // SMTPサーバの設定
+    let mut transport = if smtp.starttls {
+        SmtpTransport::starttls_relay(&smtp.host)?
+    } else {
+        SmtpTransport::builder_dangerous(&smtp.host)
+    };
+    transport = transport
+        .port(smtp.port)
+        .timeout(Some(Duration::from_secs(smtp.timeout.unwrap_or(DEFAULT_TIMEOUT))));
+    if let Some(username) = &smtp.username {
+        let password = crate::env::change_var(smtp.password.as_deref().unwrap_or(""));
+        transport = transport.credentials(Credentials::new(username.clone(), password));
+    }
+
+    // メールの送信
+    let response = transport.build().send(&message)?;
+    logger.write(&format!("mail response code({})", response.code()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+    use crate::config::{ConfigSmtp, MailLog};
+    use crate::logger::Logger;
+    use crate::mail;
+    use crate::report::{Report, ReportOutcome};
+
+    pub struct TestMail {
+        pub from: String,
+        pub to: Vec<String>,
+        pub data: String,
+    }
+
+    // 受信したメールを返すSMTPサーバを起動する
+    pub fn serve() -> (u16, JoinHandle<TestMail>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut mail = TestMail { from: String::new(), to: Vec::new(), data: String::new() };
+            write!(stream, "220 localhost ESMTP test\r\n").unwrap();
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap() == 0 {
+                    break;
+                }
+                let line = line.trim_end();
+                let command = line.to_uppercase();
+                if command.starts_with("EHLO") || command.starts_with("HELO") {
+                    write!(stream, "250 localhost\r\n").unwrap();
+                } else if command.starts_with("MAIL FROM:") {
+                    mail.from = line[10..].to_string();
+                    write!(stream, "250 OK\r\n").unwrap();
+                } else if command.starts_with("RCPT TO:") {
+                    mail.to.push(line[8..].to_string());
+                    write!(stream, "250 OK\r\n").unwrap();
+                } else if command.starts_with("DATA") {
+                    write!(stream, "354 Go ahead\r\n").unwrap();
+                    loop {
+                        let mut line = String::new();
+                        reader.read_line(&mut line).unwrap();
+                        if line == ".\r\n" {
+                            break;
+                        }
+                        mail.data.push_str(&line);
+                    }
+                    write!(stream, "250 OK\r\n").unwrap();
+                } else if command.starts_with("QUIT") {
+                    write!(stream, "221 Bye\r\n").unwrap();
+                    break;
+                } else {
+                    write!(stream, "250 OK\r\n").unwrap();
+                }
+            }
+            mail
+        });
+        (port, handle)
+    }
+
+    #[test]
+    fn test_mail_send() {
+        const LOG_DIR: &str = "test_mail_send";
+        std::fs::create_dir_all(LOG_DIR).unwrap();
+        let mut logger = Logger::new("test", LOG_DIR).unwrap();
+        for i in 0..5 {
+            logger.write(&format!("line{}", i)).unwrap();
+        }
+        let report = Report {
+            identifer: "test".to_string(),
+            command: "ls -al".to_string(),
+            outcome: Some(ReportOutcome::Failure),
+            log: logger.path.clone(),
+            ..Default::default()
+        };
+        let (port, handle) = serve();
+        let mut smtp = ConfigSmtp::new("127.0.0.1", "ezcron@example.com");
+        smtp.port = port;
+        let to = vec!["admin@example.com".to_string()];
+        let result = mail::send(&smtp, &to, MailLog::Inline, 2, &report, &mut logger);
+        let mail = handle.join().unwrap();
+        std::fs::remove_dir_all(LOG_DIR).unwrap();
+        assert!(result.is_ok());
+        assert_eq!(mail.from, "<ezcron@example.com>");
+        assert_eq!(mail.to, vec!["<admin@example.com>"]);
+        assert!(mail.data.contains(&format!("Subject: [ezcron] test failure on {}", report.hostname)));
+        assert!(mail.data.contains("command  : ls -al"));
+        assert!(!mail.data.contains("|line2"));
+        assert!(mail.data.contains("|line3"));
+        assert!(mail.data.contains("|line4"));
+    }
+
+    #[test]
+    fn test_mail_body() {
+        let report = Report {
+            identifer: "test".to_string(),
+            exitcode: 1,
+            outcome: Some(ReportOutcome::Failure),
+            ..Default::default()
+        };
+        let body = mail::body(&report, Some(&["aaa".to_string(), "bbb".to_string()]));
+        assert!(body.starts_with("process test failure."));
+        assert!(body.contains("exit code: 1\n"));
+        assert!(body.ends_with("--------\naaa\nbbb\n"));
+    }
+}
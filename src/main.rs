@@ -1,18 +1,62 @@
+pub mod check;
 pub mod config;
+pub mod control;
+pub mod daemon;
 pub mod env;
 pub mod ezcron;
+pub mod history;
 pub mod logger;
+pub mod logs;
+pub mod mail;
 pub mod pid;
 pub mod posix;
+pub mod preset;
 pub mod report;
+pub mod schedule;
+pub mod spool;
+pub mod state;
+pub mod status;
+pub mod template;
+pub mod webhook;
 
 use std::process;
+use chrono::Local;
 use ezcron::EzCron;
 use getopts::{Options, Matches};
 
+// サブコマンドの一覧
+const COMMANDS: &[(&str, &str)] = &[
+    ("run", "run the process and report the result (default)"),
+    ("explain", "print the resolved configuration (same as run --dry-run)"),
+    ("status", "print the running processes"),
+    ("logs", "print or follow the log of a run"),
+    ("kill", "terminate a running job"),
+    ("pause", "pause a running job"),
+    ("resume", "resume a paused job"),
+    ("history", "print the history of runs"),
+    ("flush-reports", "retry the spooled reports"),
+    ("check-config", "validate the configuration file"),
+    ("daemon", "run the scheduled jobs without crond"),
+];
+
 fn print_usage(program: &str, opts: &Options) {
-    let msg = format!("Usage: {} [OPTIONS] IDENTIFER -- args", program);
+    let msg = format!("Usage: {} [run] [OPTIONS] IDENTIFER [-- args]
+       {} COMMAND [OPTIONS]", program, program);
     print!("{}", opts.usage(&msg));
+    println!("\nCommands:");
+    for (name, description) in COMMANDS {
+        println!("    {:<16}{}", name, description);
+    }
+}
+
+fn parse_command(args: &mut Vec<String>) -> String {
+    // サブコマンドが無ければ従来の形式として"run"とする
+    // ("ezcron status -- cmd"のようにサブコマンドと同じ名前の識別子も従来の形式とする)
+    match args.get(1) {
+        Some(arg) if COMMANDS.iter().any(|(name, _)| name == arg)
+            && args.get(2).map(|arg| arg.as_str()) != Some("--") => args.remove(1),
+        _ => "run".to_string(),
+    }
 }
 
 fn parse_args(args: &mut Vec<String>) -> Result<Option<(Matches, Vec<String>)>, getopts::Fail> {
@@ -23,10 +67,17 @@ fn parse_args(args: &mut Vec<String>) -> Result<Option<(Matches, Vec<String>)>,
     opts
         .optmulti("r", "report", "reporting the result of process", "SCRIPT")
         .optmulti("n", "notify", "reporting the starting of process", "SCRIPT")
+        .optmulti("", "on-success", "reporting the result of process on success", "SCRIPT")
+        .optmulti("", "on-failure", "reporting the result of process on failure", "SCRIPT")
+        .optmulti("", "on-warning", "reporting the result of process on warning", "SCRIPT")
+        .optmulti("", "on-timeout", "reporting the result of process on timeout", "SCRIPT")
+        .optmulti("", "on-skip", "reporting the skipping of process", "SCRIPT")
         .optmulti("e", "env", "set environment variables", "NAME=VALUE")
         .optopt("c", "config", "specifies the ezjob configuration file\n(default '/etc/ezcron/ezcron.toml')", "FILE")
         .optopt("w", "cwd", "change current working directory", "DIRECTORY")
         .optflag("m", "multipled", "allows concurrent execution")
+        .optopt("t", "timeout", "terminate the process after the time limit", "SECONDS")
+        .optflag("", "dry-run", "print the resolved configuration without running")
         .optflag("", "version", "print version and close")
         .optflag("h", "help", "print this help menu and close");
 
@@ -47,7 +98,7 @@ fn parse_args(args: &mut Vec<String>) -> Result<Option<(Matches, Vec<String>)>,
 
     // 引数が"version"ならバージョン情報を表示する
     if matches.opt_present("version") {
-        const VERSION: &'static str = env!("CARGO_PKG_VERSION");
+        const VERSION: &str = env!("CARGO_PKG_VERSION");
         let msg = format!("ezjob {}
 Copyright (C) 2023-2023 Abtoc All Rights Reserved.
 Released under the MIT license.", VERSION);
@@ -55,8 +106,9 @@ Released under the MIT license.", VERSION);
          return Ok(None);
         }
 
-    // 引数必須の内容が無ければUsageを表示する
-    if matches.free.len() == 0 || pos >= args.len() {
+    // 識別子が無ければUsageを表示する(コマンドは設定ファイルで指定できる)
+    // 識別子の後の引数は"--"の後にのみ指定できる
+    if matches.free.len() != 1 {
         print_usage(&program, &opts);
         return Ok(None);
     }
@@ -64,18 +116,334 @@ Released under the MIT license.", VERSION);
     Ok(Some((matches, args[pos..].to_vec())))
 } 
 
-fn main() {
-    // 引数を取得する
-    let mut args: Vec<String> = std::env::args().collect();
+fn flush_reports(args: &[String]) -> i32 {
+    // オプションを設定する
+    let mut opts = Options::new();
+    opts
+        .optopt("c", "config", "specifies the ezjob configuration file\n(default '/etc/ezcron/ezcron.toml')", "FILE")
+        .optflag("h", "help", "print this help menu and close");
+    let matches = match opts.parse(args) {
+        Ok(matches) => matches,
+        Err(err) => {
+            println!("{}", err);
+            return 2;
+        },
+    };
+    if matches.opt_present("help") {
+        print!("{}", opts.usage("Usage: ezcron flush-reports [OPTIONS]"));
+        return 0;
+    }
+
+    // スプールされた通知を再送する
+    let flushed = config::load(matches.opt_str("config"))
+        .and_then(|conf| {
+            if conf.ezcron.spool_dir.is_none() {
+                return Err("[ezcron] spool_dir is not configured".into());
+            }
+            spool::flush(&conf)
+        });
+    match flushed {
+        Ok(flushed) => {
+            println!("delivered {}, retrying {}, gave up {}, broken {}", flushed.delivered, flushed.retrying, flushed.gave_up, flushed.broken);
+            0
+        },
+        Err(err) => {
+            println!("ezcron error: {}", err);
+            2
+        },
+    }
+}
+
+fn check_config(args: &[String]) -> i32 {
+    // オプションを設定する
+    let mut opts = Options::new();
+    opts
+        .optopt("c", "config", "specifies the ezjob configuration file\n(default '/etc/ezcron/ezcron.toml')", "FILE")
+        .optflag("h", "help", "print this help menu and close");
+    let matches = match opts.parse(args) {
+        Ok(matches) => matches,
+        Err(err) => {
+            println!("{}", err);
+            return 2;
+        },
+    };
+    if matches.opt_present("help") {
+        print!("{}", opts.usage("Usage: ezcron check-config [OPTIONS]"));
+        return 0;
+    }
+
+    // 設定ファイルを検証する
+    let path = config::path(matches.opt_str("config"));
+    match check::check_file(&path) {
+        Ok(problems) => {
+            print!("{}", check::format(&path, &problems));
+            if problems.is_empty() { 0 } else { 1 }
+        },
+        Err(err) => {
+            println!("ezcron error: {}", err);
+            2
+        },
+    }
+}
+
+fn daemon(args: &[String]) -> i32 {
+    // オプションを設定する
+    let mut opts = Options::new();
+    opts
+        .optopt("c", "config", "specifies the ezjob configuration file\n(default '/etc/ezcron/ezcron.toml')", "FILE")
+        .optflag("h", "help", "print this help menu and close");
+    let matches = match opts.parse(args) {
+        Ok(matches) => matches,
+        Err(err) => {
+            println!("{}", err);
+            return 2;
+        },
+    };
+    if matches.opt_present("help") {
+        print!("{}", opts.usage("Usage: ezcron daemon [OPTIONS]"));
+        return 0;
+    }
+
+    // スケジュールに従ってジョブを実行する
+    match daemon::run(&config::path(matches.opt_str("config"))) {
+        Ok(()) => 0,
+        Err(err) => {
+            println!("ezcron error: {}", err);
+            2
+        },
+    }
+}
+
+fn status(args: &[String]) -> i32 {
+    // オプションを設定する
+    let mut opts = Options::new();
+    opts
+        .optopt("c", "config", "specifies the ezjob configuration file\n(default '/etc/ezcron/ezcron.toml')", "FILE")
+        .optflag("", "json", "print in JSON format")
+        .optflag("h", "help", "print this help menu and close");
+    let matches = match opts.parse(args) {
+        Ok(matches) => matches,
+        Err(err) => {
+            println!("{}", err);
+            return 2;
+        },
+    };
+    if matches.opt_present("help") {
+        print!("{}", opts.usage("Usage: ezcron status [OPTIONS]"));
+        return 0;
+    }
+
+    // 実行中のプロセスを得る
+    let statuses = config::load(matches.opt_str("config"))
+        .and_then(|conf| status::list(&conf.ezcron.pid_dir, Local::now()));
+    match statuses {
+        Ok(statuses) if matches.opt_present("json") => {
+            println!("{}", serde_json::to_string_pretty(&statuses).unwrap_or_default());
+            0
+        },
+        Ok(statuses) => {
+            print!("{}", status::format(&statuses));
+            0
+        },
+        Err(err) => {
+            println!("ezcron error: {}", err);
+            2
+        },
+    }
+}
+
+fn logs(args: &[String]) -> i32 {
+    // オプションを設定する
+    let mut opts = Options::new();
+    opts
+        .optopt("c", "config", "specifies the ezjob configuration file\n(default '/etc/ezcron/ezcron.toml')", "FILE")
+        .optflag("l", "list", "list the runs of the job")
+        .optopt("n", "nth", "print the Nth latest log (default 1)", "NUMBER")
+        .optopt("u", "uuid", "print the log of the run", "UUID")
+        .optflag("f", "follow", "follow the log while the job is running")
+        .optflag("", "json", "print the list in JSON format")
+        .optflag("h", "help", "print this help menu and close");
+    let matches = match opts.parse(args) {
+        Ok(matches) => matches,
+        Err(err) => {
+            println!("{}", err);
+            return 2;
+        },
+    };
+    if matches.opt_present("help") || (matches.free.is_empty() && !matches.opt_present("uuid")) {
+        print!("{}", opts.usage("Usage: ezcron logs [OPTIONS] IDENTIFER\n       ezcron logs [OPTIONS] --uuid UUID"));
+        return if matches.opt_present("help") { 0 } else { 2 };
+    }
+
+    let result = (|| -> Result<(), Box<dyn std::error::Error>> {
+        let conf = config::load(matches.opt_str("config"))?;
+        let state_dir = conf.ezcron.state_dir();
+        let pid_dir = conf.ezcron.pid_dir;
+
+        // ログファイルを探す
+        let path = match (matches.opt_str("uuid"), matches.free.first()) {
+            (Some(uuid), _) => logs::find(&uuid, &state_dir, &pid_dir)?
+                .ok_or(format!("run '{}' is not found", uuid))?,
+            (None, Some(identifer)) => {
+                let entries = logs::list(identifer, &conf.ezcron.log_dir, &state_dir, &pid_dir)?;
+                if matches.opt_present("list") {
+                    if matches.opt_present("json") {
+                        println!("{}", serde_json::to_string_pretty(&entries)?);
+                    } else {
+                        print!("{}", logs::format(&entries));
+                    }
+                    return Ok(());
+                }
+                let nth: usize = matches.opt_str("nth").map_or(Ok(1), |nth| nth.parse())?;
+                entries.into_iter().rev().nth(nth.saturating_sub(1))
+                    .ok_or(format!("log of '{}' is not found", identifer))?
+                    .path
+            },
+            (None, None) => unreachable!(),
+        };
+
+        // ログファイルを出力する
+        let mut stdout = std::io::stdout();
+        if matches.opt_present("follow") {
+            logs::follow(&path, &mut stdout, || logs::is_running(&path, &pid_dir))?;
+        } else {
+            logs::follow(&path, &mut stdout, || false)?;
+        }
+        Ok(())
+    })();
+    match result {
+        Ok(()) => 0,
+        Err(err) => {
+            println!("ezcron error: {}", err);
+            2
+        },
+    }
+}
+
+fn control(command: &str, args: &[String]) -> i32 {
+    // オプションを設定する
+    let mut opts = Options::new();
+    opts.optopt("c", "config", "specifies the ezjob configuration file\n(default '/etc/ezcron/ezcron.toml')", "FILE");
+    if command == "kill" {
+        opts.optopt("g", "grace", &format!("seconds to wait before sending SIGKILL (default {})", control::DEFAULT_GRACE), "SECONDS");
+    }
+    opts.optflag("h", "help", "print this help menu and close");
+    let matches = match opts.parse(args) {
+        Ok(matches) => matches,
+        Err(err) => {
+            println!("{}", err);
+            return 2;
+        },
+    };
+    if matches.opt_present("help") || matches.free.len() != 1 {
+        print!("{}", opts.usage(&format!("Usage: ezcron {} [OPTIONS] IDENTIFER|UUID", command)));
+        return if matches.opt_present("help") { 0 } else { 2 };
+    }
+
+    let result = (|| -> Result<String, Box<dyn std::error::Error>> {
+        let conf = config::load(matches.opt_str("config"))?;
+        let pid_dir = conf.ezcron.pid_dir;
+        let status = control::find(&matches.free[0], &pid_dir)?;
+        let message = match command {
+            "kill" => {
+                let grace: u64 = matches.opt_str("grace").map_or(Ok(control::DEFAULT_GRACE), |grace| grace.parse())?;
+                if control::kill(&status, &pid_dir, grace)? { "killed" } else { "terminated" }
+            },
+            "pause" => {
+                control::pause(&status, &pid_dir)?;
+                "paused"
+            },
+            _ => {
+                control::resume(&status, &pid_dir)?;
+                "resumed"
+            },
+        };
+        Ok(format!("{} {} (pid {})", message, status.identifer, status.pid))
+    })();
+    match result {
+        Ok(message) => {
+            println!("{}", message);
+            0
+        },
+        Err(err) => {
+            println!("ezcron error: {}", err);
+            2
+        },
+    }
+}
+
+fn history(args: &[String]) -> i32 {
+    // オプションを設定する
+    let mut opts = Options::new();
+    opts
+        .optopt("c", "config", "specifies the ezjob configuration file\n(default '/etc/ezcron/ezcron.toml')", "FILE")
+        .optmulti("j", "job", "show only the specified job", "IDENTIFER")
+        .optopt("s", "since", "show runs since the date or the period\n(e.g. '2023-12-11', '7d', '12h')", "SINCE")
+        .optopt("n", "limit", "number of recent runs to show (default 10)", "NUMBER")
+        .optflag("", "json", "print in JSON format")
+        .optflag("h", "help", "print this help menu and close");
+    let matches = match opts.parse(args) {
+        Ok(matches) => matches,
+        Err(err) => {
+            println!("{}", err);
+            return 2;
+        },
+    };
+    if matches.opt_present("help") {
+        print!("{}", opts.usage("Usage: ezcron history [OPTIONS]"));
+        return 0;
+    }
+
+    // 実行履歴を集計する
+    let stats = (|| -> Result<Vec<history::Stats>, Box<dyn std::error::Error>> {
+        let conf = config::load(matches.opt_str("config"))?;
+        let state_dir = conf.ezcron.state_dir();
+        let since = matches.opt_str("since")
+            .map(|since| history::parse_since(&since, Local::now()))
+            .transpose()?;
+        let limit = matches.opt_str("limit").map_or(Ok(10), |limit| limit.parse())?;
+        let identifers = match matches.opt_strs("job") {
+            jobs if jobs.is_empty() => history::identifers(&state_dir)?,
+            jobs => jobs,
+        };
+        let mut stats = Vec::new();
+        for identifer in identifers {
+            let records: Vec<history::RunRecord> = history::load(&identifer, &state_dir)?
+                .into_iter()
+                .filter(|record| since.is_none_or(|since| record.report.start_at >= since))
+                .collect();
+            stats.push(history::stats(&identifer, &records, limit));
+        }
+        Ok(stats)
+    })();
+    match stats {
+        Ok(stats) if matches.opt_present("json") => {
+            println!("{}", serde_json::to_string_pretty(&stats).unwrap_or_default());
+            0
+        },
+        Ok(stats) => {
+            for stats in stats {
+                print!("{}", history::format(&stats));
+            }
+            0
+        },
+        Err(err) => {
+            println!("ezcron error: {}", err);
+            2
+        },
+    }
+}
+
+fn run(args: &mut Vec<String>) -> i32 {
     // 引数をチェックする
-    let Some((matches, args)) = (match parse_args(&mut args) {
+    let Some((matches, args)) = (match parse_args(args) {
         Ok(result) => result,
         Err(err) => {
             println!("{}", err);
-            process::exit(2);
+            return 2;
         },
     }) else {
-        process::exit(2);
+        return 2;
     };
 
     //  実行する
@@ -83,18 +451,72 @@ fn main() {
         Ok(main) => main,
         Err(err) => {
             println!("ezcron error: {}", err);
-            process::exit(2);        
+            return 2;
         },
     };
-    if let Err(err) = main.run(&args) {
-        println!("ezcron error: '{}", err);
-        process::exit(2);
+    if matches.opt_present("dry-run") {
+        print!("{}", main.explain(&args));
+        return 0;
     }
+    match main.run(&args) {
+        Ok(code) => code,
+        Err(err) => {
+            println!("ezcron error: '{}", err);
+            2
+        },
+    }
+}
+
+fn main() {
+    // 引数を取得する
+    let mut args: Vec<String> = std::env::args().collect();
+
+    // サブコマンドを実行する
+    let code = match parse_command(&mut args).as_str() {
+        "explain" => {
+            args.insert(1, "--dry-run".to_string());
+            run(&mut args)
+        },
+        "status" => status(&args[1..]),
+        "logs" => logs(&args[1..]),
+        command @ ("kill" | "pause" | "resume") => control(command, &args[1..]),
+        "history" => history(&args[1..]),
+        "flush-reports" => flush_reports(&args[1..]),
+        "check-config" => check_config(&args[1..]),
+        "daemon" => daemon(&args[1..]),
+        _ => run(&mut args),
+    };
+    process::exit(code);
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::useless_vec)]
 mod tests {
-    use crate::parse_args;
+    use crate::{parse_args, parse_command};
+
+    #[test]
+    fn test_parse_command() {
+        // サブコマンドを取り除いて返す
+        let mut args = ["program", "run", "test", "--", "ls"].iter().map(|&s| s.to_string()).collect();
+        assert_eq!(parse_command(&mut args), "run");
+        assert_eq!(args, vec!["program", "test", "--", "ls"]);
+        let mut args = ["program", "history", "--json"].iter().map(|&s| s.to_string()).collect();
+        assert_eq!(parse_command(&mut args), "history");
+        assert_eq!(args, vec!["program", "--json"]);
+        // 従来の形式は"run"とする
+        let mut args = ["program", "-r", "report.sh", "test", "--", "ls"].iter().map(|&s| s.to_string()).collect();
+        assert_eq!(parse_command(&mut args), "run");
+        assert_eq!(args.len(), 6);
+        let mut args = ["program"].iter().map(|&s| s.to_string()).collect();
+        assert_eq!(parse_command(&mut args), "run");
+        // サブコマンドと同じ名前の識別子
+        let mut args = ["program", "status", "--", "ls"].iter().map(|&s| s.to_string()).collect();
+        assert_eq!(parse_command(&mut args), "run");
+        assert_eq!(args, vec!["program", "status", "--", "ls"]);
+        let mut args = ["program", "run", "--", "ls"].iter().map(|&s| s.to_string()).collect();
+        assert_eq!(parse_command(&mut args), "run");
+        assert_eq!(args, vec!["program", "run", "--", "ls"]);
+    }
 
     #[test]
     fn test_parse_args_none() {
@@ -152,4 +574,22 @@ mod tests {
         assert_eq!(matches.free.len(), 1);
         assert_eq!(args, vec!["ls", "-al"]);
     }
+
+    #[test]
+    fn test_parse_args_without_command() {
+        // コマンドを設定ファイルで指定する場合は識別子だけで良い
+        let mut args = ["program", "-c", "test.conf", "job01"].iter().map(|&s| s.to_string()).collect();
+        let Ok(Some((matches, args))) = parse_args(&mut args) else { panic!("impossible error") };
+        assert_eq!(matches.free, vec!["job01"]);
+        assert!(args.is_empty());
+    }
+
+    #[test]
+    fn test_parse_args_extra() {
+        // "--"の無い識別子の後の引数は受け付けない
+        let mut args = ["program", "job01", "extra"].iter().map(|&s| s.to_string()).collect();
+        assert!(parse_args(&mut args).unwrap().is_none());
+        let mut args = ["program", "job01", "extra", "--", "ls"].iter().map(|&s| s.to_string()).collect();
+        assert!(parse_args(&mut args).unwrap().is_none());
+    }
 }
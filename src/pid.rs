@@ -1,11 +1,40 @@
-use std::fs::File;
-use std::io::Write;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
 use std::path::{Path, PathBuf};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::report::{Report, ReportAction};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RunInfo {
+    pub identifer: String,
+    pub uuid: String,
+    pub pid: u32,
+    pub start_at: DateTime<Local>,
+    pub command: String,
+    pub log: String,
+}
+
+impl From<&Report> for RunInfo {
+    fn from(report: &Report) -> Self {
+        Self {
+            identifer: report.identifer.clone(),
+            uuid: report.uuid.to_string(),
+            pid: report.pid,
+            start_at: report.start_at,
+            command: report.command.clone(),
+            log: report.log.clone(),
+        }
+    }
+}
 
 pub struct Pid {
     multipled: bool,
     path: Box<PathBuf>,
     pid: u32,
+    info_path: Option<PathBuf>,
+    actions_path: Option<PathBuf>,
 }
 
 impl Pid {
@@ -13,11 +42,16 @@ impl Pid {
         let path = Path::new(pid_dir)
             .join(format!("{}.pid", identifer));
         Self {
-            multipled: multipled,
+            multipled,
             path: Box::new(path),
             pid: 0,
+            info_path: None,
+            actions_path: None,
         }
     }
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
     pub fn is_exists(&self) -> bool {
         !self.multipled && self.path.is_file()
     }
@@ -26,10 +60,24 @@ impl Pid {
         if !self.multipled {
             let mut fs = File::create(self.path.as_path())?;
             let pid = format!("{}", pid);
-            fs.write(pid.as_bytes()).unwrap();
+            fs.write_all(pid.as_bytes()).unwrap();
         }
         Ok(())
     }
+    pub fn write_info(&mut self, info: &RunInfo) -> Result<(), Box<dyn std::error::Error>> {
+        // 実行中の情報を書き込む(同時実行の場合も実行ごとに作成する)
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+        let path = dir.join(format!("{}-{}.json", info.identifer, info.uuid));
+        fs::write(&path, serde_json::to_string(info)?)?;
+        self.info_path = Some(path);
+        self.actions_path = Some(actions_path(&dir.to_string_lossy(), &info.identifer, &info.uuid));
+        Ok(())
+    }
+    pub fn actions(&self) -> Vec<ReportAction> {
+        // 他のプロセスから行われた操作を得る
+        self.actions_path.as_ref()
+            .map_or(Vec::new(), |path| load_actions(path))
+    }
 }
 
 impl Drop for Pid {
@@ -37,10 +85,41 @@ impl Drop for Pid {
         if self.pid > 0 && self.path.is_file() {
             std::fs::remove_file(self.path.as_path()).unwrap();
         }
+        if let Some(info_path) = &self.info_path {
+            fs::remove_file(info_path).ok();
+        }
+        if let Some(actions_path) = &self.actions_path {
+            fs::remove_file(actions_path).ok();
+        }
     }
 }
 
+pub fn load_info(path: &Path) -> Result<RunInfo, Box<dyn std::error::Error>> {
+    let json = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+pub fn actions_path(pid_dir: &str, identifer: &str, uuid: &str) -> PathBuf {
+    Path::new(pid_dir)
+        .join(format!("{}-{}.actions.jsonl", identifer, uuid))
+}
+
+pub fn append_action(path: &Path, action: &ReportAction) -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(format!("{}\n", serde_json::to_string(action)?).as_bytes())?;
+    Ok(())
+}
+
+pub fn load_actions(path: &Path) -> Vec<ReportAction> {
+    let Ok(file) = File::open(path) else { return Vec::new(); };
+    BufReader::new(file).lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect()
+}
+
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use std::path::Path;
     use crate::pid::Pid;
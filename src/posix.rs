@@ -1,5 +1,20 @@
+use std::ffi::CString;
 use std::fs::File;
 use std::os::fd::FromRawFd;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::report::ReportUsage;
+
+// 受け取ったシグナル
+static SIGNALS: [AtomicBool; 32] = [const { AtomicBool::new(false) }; 32];
+
+extern "C" fn signal_handler(sig: libc::c_int) {
+    if let Some(received) = SIGNALS.get(sig as usize) {
+        received.store(true, Ordering::SeqCst);
+    }
+}
 
 fn check_err<T: Ord + Default>(num: T) -> std::io::Result<T> {
     if num < T::default() {
@@ -14,8 +29,63 @@ pub fn pipe() -> std::io::Result<(File, File)> {
     Ok(unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) })
 }
 
+pub fn kill(pid: u32, sig: libc::c_int) -> std::io::Result<()> {
+    check_err(unsafe { libc::kill(pid as libc::pid_t, sig) })?;
+    Ok(())
+}
+
+pub fn kill_group(pgid: u32, sig: libc::c_int) -> std::io::Result<()> {
+    check_err(unsafe { libc::kill(-(pgid as libc::pid_t), sig) })?;
+    Ok(())
+}
+
+pub fn is_writable(path: &Path) -> bool {
+    // 実行ユーザーが書き込めるか確認する
+    let Ok(path) = CString::new(path.as_os_str().as_bytes()) else { return false; };
+    unsafe { libc::access(path.as_ptr(), libc::W_OK) == 0 }
+}
+
+pub fn catch_signal(sig: libc::c_int) -> std::io::Result<()> {
+    // シグナルを受け取ったことを記録するだけのハンドラを設定する
+    let handler = signal_handler as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    if unsafe { libc::signal(sig, handler) } == libc::SIG_ERR {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+pub fn is_ignored(sig: libc::c_int) -> bool {
+    // 起動元で無視するよう設定されたシグナルか確認する
+    let mut action: libc::sigaction = unsafe { std::mem::zeroed() };
+    unsafe { libc::sigaction(sig, std::ptr::null(), &mut action) == 0 && action.sa_sigaction == libc::SIG_IGN }
+}
+
+pub fn default_signal(sig: libc::c_int) -> std::io::Result<()> {
+    if unsafe { libc::signal(sig, libc::SIG_DFL) } == libc::SIG_ERR {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+pub fn take_signal(sig: libc::c_int) -> bool {
+    SIGNALS.get(sig as usize)
+        .is_some_and(|received| received.swap(false, Ordering::SeqCst))
+}
+
+pub fn rusage_children() -> std::io::Result<ReportUsage> {
+    // 終了を待った子プロセスの資源使用量を得る
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    check_err(unsafe { libc::getrusage(libc::RUSAGE_CHILDREN, &mut usage) })?;
+    let seconds = |tv: libc::timeval| tv.tv_sec as f64 + tv.tv_usec as f64 / 1_000_000.0;
+    Ok(ReportUsage {
+        user_time: seconds(usage.ru_utime),
+        system_time: seconds(usage.ru_stime),
+        max_rss: usage.ru_maxrss as u64,
+    })
+}
 
 #[cfg(test)]
+#[allow(clippy::char_lit_as_u8, clippy::unused_io_amount)]
 mod tests {
     use std::io::{Read, Write};
     use crate::posix;
@@ -0,0 +1,161 @@
+use serde_json::{json, Value};
+
+use crate::config::Preset;
+use crate::report::{Report, ReportOutcome};
+
+pub const DEFAULT_SNIPPET_LINES: usize = 20;
+const MAX_SNIPPET_CHARS: usize = 1500;
+
+fn outcome_of(report: &Report) -> &'static str {
+    report.outcome.map_or("running", |outcome| outcome.as_str())
+}
+
+fn color(report: &Report) -> u32 {
+    match report.outcome {
+        Some(ReportOutcome::Success) => 0x2eb886,
+        Some(ReportOutcome::Warning) => 0xdaa038,
+        Some(ReportOutcome::Failure) | Some(ReportOutcome::Timeout) => 0xa30200,
+        Some(ReportOutcome::Skipped) | None => 0x808080,
+    }
+}
+
+fn emoji(report: &Report) -> &'static str {
+    match report.outcome {
+        Some(ReportOutcome::Success) => "\u{2705}",
+        Some(ReportOutcome::Warning) => "\u{26a0}\u{fe0f}",
+        Some(ReportOutcome::Failure) => "\u{274c}",
+        Some(ReportOutcome::Timeout) => "\u{23f0}",
+        Some(ReportOutcome::Skipped) => "\u{23ed}\u{fe0f}",
+        None => "\u{1f680}",
+    }
+}
+
+fn title(report: &Report) -> String {
+    format!("{} {} {} on {}", emoji(report), report.identifer, outcome_of(report), report.hostname)
+}
+
+fn facts(report: &Report) -> Vec<(&'static str, String)> {
+    let duration = report.end_at
+        .map_or("".to_string(), |end_at| format!("{}s", (end_at - report.start_at).num_seconds()));
+    vec![
+        ("Host", report.hostname.clone()),
+        ("Command", report.command.clone()),
+        ("Exit code", report.exitcode.to_string()),
+        ("Outcome", outcome_of(report).to_string()),
+        ("Duration", duration),
+        ("Message", report.result.clone()),
+    ]
+}
+
+pub fn snippet(lines: &[String]) -> String {
+    // ジョブの出力の末尾を、長すぎる場合は先頭を切り詰める
+    let snippet = lines.join("\n");
+    let count = snippet.chars().count();
+    if count <= MAX_SNIPPET_CHARS {
+        return snippet;
+    }
+    let snippet: String = snippet.chars().skip(count - MAX_SNIPPET_CHARS).collect();
+    format!("...{}", snippet)
+}
+
+fn escape_html(text: &str) -> String {
+    // <pre>の中にそのまま入れられるようにする
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn render(preset: Preset, report: &Report, snippet: &str) -> Value {
+    let title = title(report);
+    let color = color(report);
+    let facts = facts(report);
+    match preset {
+        Preset::Slack | Preset::Mattermost => json!({
+            "text": title,
+            "attachments": [{
+                "color": format!("#{:06x}", color),
+                "fields": facts.iter()
+                    .map(|(name, value)| json!({"title": name, "value": value, "short": true}))
+                    .collect::<Vec<Value>>(),
+                "text": format!("```\n{}\n```", snippet),
+                "ts": report.start_at.timestamp(),
+            }],
+        }),
+        Preset::Discord => json!({
+            "content": title,
+            "embeds": [{
+                "title": title,
+                "color": color,
+                "fields": facts.iter()
+                    .map(|(name, value)| json!({"name": name, "value": value, "inline": true}))
+                    .collect::<Vec<Value>>(),
+                "description": format!("```\n{}\n```", snippet),
+                "timestamp": report.start_at.to_rfc3339(),
+            }],
+        }),
+        Preset::Teams => json!({
+            "@type": "MessageCard",
+            "@context": "https://schema.org/extensions",
+            "themeColor": format!("{:06X}", color),
+            "summary": title,
+            "title": title,
+            "sections": [{
+                "facts": facts.iter()
+                    .map(|(name, value)| json!({"name": name, "value": value}))
+                    .collect::<Vec<Value>>(),
+                "text": format!("<pre>{}</pre>", escape_html(snippet)),
+            }],
+        }),
+        Preset::GoogleChat => {
+            let facts = facts.iter()
+                .map(|(name, value)| format!("{}: {}", name, value))
+                .collect::<Vec<String>>()
+                .join("\n");
+            json!({
+                "text": format!("*{}*\n{}\n```\n{}\n```", title, facts, snippet),
+            })
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::config::Preset;
+    use crate::preset;
+    use crate::report::{Report, ReportOutcome};
+
+    #[test]
+    fn test_preset_snippet() {
+        let lines = vec!["aaa".to_string(), "bbb".to_string()];
+        assert_eq!(preset::snippet(&lines), "aaa\nbbb");
+        let lines = vec!["x".repeat(2000)];
+        let snippet = preset::snippet(&lines);
+        assert!(snippet.starts_with("..."));
+        assert_eq!(snippet.chars().count(), 1503);
+    }
+
+    #[test]
+    fn test_preset_render() {
+        let report = Report {
+            identifer: "test".to_string(),
+            outcome: Some(ReportOutcome::Failure),
+            ..Default::default()
+        };
+        let slack = preset::render(Preset::Slack, &report, "aaa");
+        assert!(slack["text"].as_str().unwrap().contains("test failure"));
+        assert_eq!(slack["attachments"][0]["color"], "#a30200");
+        assert_eq!(slack["attachments"][0]["text"], "```\naaa\n```");
+        let discord = preset::render(Preset::Discord, &report, "aaa");
+        assert_eq!(discord["embeds"][0]["color"], 0xa30200);
+        let teams = preset::render(Preset::Teams, &report, "aaa");
+        assert_eq!(teams["themeColor"], "A30200");
+        assert_eq!(teams["sections"][0]["text"], "<pre>aaa</pre>");
+        let teams = preset::render(Preset::Teams, &report, "<b>a & \"b\"</b>");
+        assert_eq!(teams["sections"][0]["text"], "<pre>&lt;b&gt;a &amp; &quot;b&quot;&lt;/b&gt;</pre>");
+        let googlechat = preset::render(Preset::GoogleChat, &report, "aaa");
+        assert!(googlechat["text"].as_str().unwrap().ends_with("```\naaa\n```"));
+        let mattermost = preset::render(Preset::Mattermost, &report, "aaa");
+        assert_eq!(mattermost["attachments"][0]["fields"][0]["title"], "Host");
+    }
+}
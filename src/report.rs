@@ -1,15 +1,104 @@
+use std::time::Instant;
 use chrono::{DateTime, Local};
 use gethostname::gethostname;
-use serde::Serialize;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum ReportStatus {
     Running,
     Finished,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportOutcome {
+    Success,
+    Warning,
+    Failure,
+    Timeout,
+    Skipped,
+}
+
+impl ReportOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReportOutcome::Success => "success",
+            ReportOutcome::Warning => "warning",
+            ReportOutcome::Failure => "failure",
+            ReportOutcome::Timeout => "timeout",
+            ReportOutcome::Skipped => "skipped",
+        }
+    }
+    pub fn is_failure(&self) -> bool {
+        matches!(self, ReportOutcome::Failure | ReportOutcome::Timeout)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct ReporterResult {
+    pub reporter: String,
+    pub exitcode: Option<u32>,
+    pub success: bool,
+    pub timed_out: bool,
+    pub duration: f64,
+    pub error: Option<String>,
+    #[serde(default)]
+    pub detached: bool,
+}
+
+impl ReporterResult {
+    pub fn new(reporter: &str) -> Self {
+        Self {
+            reporter: reporter.to_string(),
+            ..Default::default()
+        }
+    }
+    pub fn succeeded(mut self, started: Instant) -> Self {
+        self.success = true;
+        self.duration = started.elapsed().as_secs_f64();
+        self
+    }
+    pub fn failed(mut self, error: &str, started: Instant) -> Self {
+        self.success = false;
+        self.error = Some(error.to_string());
+        self.duration = started.elapsed().as_secs_f64();
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub struct ReportUsage {
+    pub user_time: f64,
+    pub system_time: f64,
+    pub max_rss: u64,
+}
+
+impl ReportUsage {
+    pub fn since(&self, before: &ReportUsage) -> Self {
+        // CPU時間は差分、最大常駐サイズはそのままとする
+        Self {
+            user_time: (self.user_time - before.user_time).max(0.0),
+            system_time: (self.system_time - before.system_time).max(0.0),
+            max_rss: self.max_rss,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct ReportAction {
+    pub action: String,
+    pub at: DateTime<Local>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ReportMatch {
+    pub line: usize,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Report {
     pub identifer: String,
     pub uuid: Uuid,
@@ -20,9 +109,74 @@ pub struct Report {
     pub result: String,
     pub pid: u32,
     pub status: ReportStatus,
+    pub outcome: Option<ReportOutcome>,
+    pub previous_outcome: Option<ReportOutcome>,
+    pub consecutive_failures: u32,
+    pub suppressed_count: u32,
     pub log: String,
     pub start_at: DateTime<Local>,
     pub end_at: Option<DateTime<Local>>, 
+    pub fail_matches: Vec<ReportMatch>,
+    pub warn_matches: Vec<ReportMatch>,
+    #[serde(default)]
+    pub output_head: Vec<String>,
+    #[serde(default)]
+    pub output_tail: Vec<String>,
+    #[serde(default)]
+    pub output_lines: usize,
+    #[serde(default)]
+    pub output_bytes: u64,
+    #[serde(default)]
+    pub usage: Option<ReportUsage>,
+    #[serde(default)]
+    pub actions: Vec<ReportAction>,
+}
+
+impl Report {
+    pub fn expand(&self, arg: &str) -> String {
+        // "{outcome}"のような項目名をレポートの値に置き換える
+        let env = self.env();
+        let re = Regex::new(r"\{([a-z_]+)\}").unwrap();
+        re.replace_all(arg, |caps: &Captures| {
+            let name = match &caps[1] {
+                "identifer" => "identifier",
+                name => name,
+            };
+            let name = format!("EZCRON_{}", name.to_uppercase());
+            env.iter()
+                .find(|(key, _)| *key == name)
+                .map_or(caps[0].to_string(), |(_, value)| value.clone())
+        }).into_owned()
+    }
+    pub fn env(&self) -> Vec<(String, String)> {
+        // 環境変数としてスクリプトに渡す値
+        let outcome = self.outcome.map_or("", |outcome| outcome.as_str());
+        let previous_outcome = self.previous_outcome.map_or("", |outcome| outcome.as_str());
+        let end_at = self.end_at.map_or("".to_string(), |end_at| end_at.to_rfc3339());
+        let duration = self.end_at.map_or("".to_string(), |end_at| {
+            format!("{:.3}", (end_at - self.start_at).num_milliseconds() as f64 / 1000.0)
+        });
+        vec![
+            ("EZCRON_IDENTIFIER", self.identifer.clone()),
+            ("EZCRON_UUID", self.uuid.to_string()),
+            ("EZCRON_HOSTNAME", self.hostname.clone()),
+            ("EZCRON_COMMAND", self.command.clone()),
+            ("EZCRON_EXITCODE", self.exitcode.to_string()),
+            ("EZCRON_RESULT", self.result.clone()),
+            ("EZCRON_PID", self.pid.to_string()),
+            ("EZCRON_STATUS", format!("{:?}", self.status)),
+            ("EZCRON_OUTCOME", outcome.to_string()),
+            ("EZCRON_PREVIOUS_OUTCOME", previous_outcome.to_string()),
+            ("EZCRON_CONSECUTIVE_FAILURES", self.consecutive_failures.to_string()),
+            ("EZCRON_SUPPRESSED_COUNT", self.suppressed_count.to_string()),
+            ("EZCRON_LOG", self.log.clone()),
+            ("EZCRON_START_AT", self.start_at.to_rfc3339()),
+            ("EZCRON_END_AT", end_at),
+            ("EZCRON_DURATION", duration),
+            ("EZCRON_OUTPUT_LINES", self.output_lines.to_string()),
+            ("EZCRON_OUTPUT_BYTES", self.output_bytes.to_string()),
+        ].into_iter().map(|(name, value)| (name.to_string(), value)).collect()
+    }
 }
 
 impl Default for Report {
@@ -37,9 +191,21 @@ impl Default for Report {
             result: String::default(),
             pid: 0,
             status: ReportStatus::Running,
+            outcome: None,
+            previous_outcome: None,
+            consecutive_failures: 0,
+            suppressed_count: 0,
             log: String::default(),
             start_at: Local::now(),
             end_at: None,
+            fail_matches: Vec::<ReportMatch>::new(),
+            warn_matches: Vec::<ReportMatch>::new(),
+            output_head: Vec::<String>::new(),
+            output_tail: Vec::<String>::new(),
+            output_lines: 0,
+            output_bytes: 0,
+            usage: None,
+            actions: Vec::<ReportAction>::new(),
         }        
     }
 }
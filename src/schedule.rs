@@ -0,0 +1,193 @@
+use chrono::{DateTime, Datelike, Duration, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+
+// 次の実行日時を探す期間(日)
+const SEARCH_DAYS: i64 = 366 * 5;
+// 夏時間で存在しない時刻の後に、存在する時刻を探す期間(分)
+const GAP_MINUTES: i64 = 24 * 60;
+
+const MONTHS: &[&str] = &["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+const WEEKDAYS: &[&str] = &["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+// cronの1項目(該当する値をビットで持つ)
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Field {
+    bits: u64,
+    any: bool,
+}
+
+impl Field {
+    fn parse(text: &str, min: u32, max: u32, names: &[&str], name_base: u32) -> Result<Self, String> {
+        let value = |text: &str| -> Result<u32, String> {
+            if let Some(pos) = names.iter().position(|name| name.eq_ignore_ascii_case(text)) {
+                return Ok(pos as u32 + name_base);
+            }
+            let value: u32 = text.parse().map_err(|_| format!("invalid value '{}'", text))?;
+            if value < min || value > max {
+                return Err(format!("value '{}' is out of range {}-{}", value, min, max));
+            }
+            Ok(value)
+        };
+        let mut bits = 0;
+        for item in text.split(',') {
+            // "範囲/間隔"に分ける
+            let (range, step) = match item.split_once('/') {
+                Some((range, step)) => (range, step.parse::<u32>().ok().filter(|step| *step > 0)
+                    .ok_or(format!("invalid step '{}'", step))?),
+                None => (item, 1),
+            };
+            let (start, end) = match range.split_once('-') {
+                _ if range == "*" => (min, max),
+                Some((start, end)) => (value(start)?, value(end)?),
+                None if item.contains('/') => (value(range)?, max),
+                None => (value(range)?, value(range)?),
+            };
+            if start > end {
+                return Err(format!("invalid range '{}'", range));
+            }
+            for value in (start..=end).step_by(step as usize) {
+                bits |= 1 << value;
+            }
+        }
+        Ok(Self { bits, any: text.starts_with('*') })
+    }
+    fn contains(&self, value: u32) -> bool {
+        self.bits & (1 << value) != 0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    minute: Field,
+    hour: Field,
+    day: Field,
+    month: Field,
+    weekday: Field,
+}
+
+impl Schedule {
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        // "分 時 日 月 曜日"の形式、または"@daily"などの別名
+        let expression = match expression.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            expression => expression,
+        };
+        let fields = expression.split_whitespace().collect::<Vec<&str>>();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!("'{}' must have 5 fields", expression));
+        };
+        let mut weekday = Field::parse(weekday, 0, 7, WEEKDAYS, 0)?;
+        // 日曜日は0と7のどちらでも指定できる
+        if weekday.contains(7) {
+            weekday.bits |= 1;
+        }
+        Ok(Self {
+            minute: Field::parse(minute, 0, 59, &[], 0)?,
+            hour: Field::parse(hour, 0, 23, &[], 0)?,
+            day: Field::parse(day, 1, 31, &[], 0)?,
+            month: Field::parse(month, 1, 12, MONTHS, 1)?,
+            weekday,
+        })
+    }
+    fn is_day(&self, date: NaiveDate) -> bool {
+        // 日と曜日の両方を指定した場合はどちらかに該当すれば良い(cronと同じ)
+        let day = self.day.contains(date.day());
+        let weekday = self.weekday.contains(date.weekday().num_days_from_sunday());
+        match (self.day.any, self.weekday.any) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+    pub fn next_after<Tz: TimeZone>(&self, after: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        // 指定日時の次の分から該当する日時を探す
+        let timezone = after.timezone();
+        let local = after.naive_local();
+        let mut time = local.date().and_hms_opt(local.hour(), local.minute(), 0)? + Duration::minutes(1);
+        let limit = time + Duration::days(SEARCH_DAYS);
+        let next_day = |time: NaiveDateTime| time.date().succ_opt().and_then(|date| date.and_hms_opt(0, 0, 0));
+        while time < limit {
+            if !self.month.contains(time.month()) {
+                let (year, month) = if time.month() == 12 { (time.year() + 1, 1) } else { (time.year(), time.month() + 1) };
+                time = NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+            } else if !self.is_day(time.date()) {
+                time = next_day(time)?;
+            } else if !self.hour.contains(time.hour()) {
+                time = time.date().and_hms_opt(time.hour(), 0, 0)? + Duration::hours(1);
+            } else if !self.minute.contains(time.minute()) {
+                time += Duration::minutes(1);
+            } else {
+                // 夏時間で存在しない時刻はその後の最初の時刻とし(cronと同じ)、重複する時刻は早い方とする
+                let next = match timezone.from_local_datetime(&time) {
+                    LocalResult::None => (1..=GAP_MINUTES)
+                        .find_map(|minutes| timezone.from_local_datetime(&(time + Duration::minutes(minutes))).earliest()),
+                    result => result.earliest(),
+                };
+                match next {
+                    Some(next) if next > *after => return Some(next),
+                    _ => time += Duration::minutes(1),
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, FixedOffset, TimeZone};
+    use chrono_tz::Tz;
+    use crate::schedule::Schedule;
+
+    fn next(expression: &str, after: &str) -> String {
+        let after = DateTime::parse_from_rfc3339(after).unwrap();
+        Schedule::parse(expression).unwrap().next_after(&after).unwrap().to_rfc3339()
+    }
+
+    #[test]
+    fn test_schedule_next() {
+        assert_eq!(next("* * * * *", "2023-12-11T00:54:18+09:00"), "2023-12-11T00:55:00+09:00");
+        assert_eq!(next("*/15 * * * *", "2023-12-11T00:54:18+09:00"), "2023-12-11T01:00:00+09:00");
+        assert_eq!(next("30 2 * * *", "2023-12-11T02:30:00+09:00"), "2023-12-12T02:30:00+09:00");
+        assert_eq!(next("0 0 1 jan *", "2023-12-11T00:00:00+09:00"), "2024-01-01T00:00:00+09:00");
+        assert_eq!(next("0 9 * * mon-fri", "2023-12-15T10:00:00+09:00"), "2023-12-18T09:00:00+09:00");
+        assert_eq!(next("0 0 29 2 *", "2023-03-01T00:00:00+09:00"), "2024-02-29T00:00:00+09:00");
+        assert_eq!(next("@weekly", "2023-12-11T00:00:00+09:00"), "2023-12-17T00:00:00+09:00");
+        assert_eq!(next("0 0 * * 7", "2023-12-11T00:00:00+09:00"), "2023-12-17T00:00:00+09:00");
+        // 日と曜日の両方を指定した場合はどちらか早い方
+        assert_eq!(next("0 0 20 * sun", "2023-12-11T00:00:00+09:00"), "2023-12-17T00:00:00+09:00");
+        assert_eq!(next("0 0 13 * sun", "2023-12-11T00:00:00+09:00"), "2023-12-13T00:00:00+09:00");
+        assert_eq!(next("0 12 10-20/5 * *", "2023-12-11T00:00:00+09:00"), "2023-12-15T12:00:00+09:00");
+    }
+
+    #[test]
+    fn test_schedule_timezone() {
+        // 夏時間で存在しない時刻は、夏時間になった直後に実行する
+        let tz: Tz = "America/New_York".parse().unwrap();
+        let after = tz.with_ymd_and_hms(2024, 3, 9, 3, 0, 0).unwrap();
+        let schedule = Schedule::parse("30 2 * * *").unwrap();
+        assert_eq!(schedule.next_after(&after).unwrap().to_rfc3339(), "2024-03-10T03:00:00-04:00");
+        let offset = FixedOffset::east_opt(0).unwrap();
+        let after = offset.with_ymd_and_hms(2024, 3, 10, 0, 0, 0).unwrap();
+        let next = schedule.next_after(&after.with_timezone(&tz)).unwrap();
+        assert_eq!(next.to_rfc3339(), "2024-03-10T03:00:00-04:00");
+        assert_eq!(schedule.next_after(&next).unwrap().to_rfc3339(), "2024-03-11T02:30:00-04:00");
+        // 存在しない時刻に複数該当しても1回だけ実行する
+        let schedule = Schedule::parse("*/15 * * * *").unwrap();
+        let after = tz.with_ymd_and_hms(2024, 3, 10, 1, 50, 0).unwrap();
+        let next = schedule.next_after(&after).unwrap();
+        assert_eq!(next.to_rfc3339(), "2024-03-10T03:00:00-04:00");
+        assert_eq!(schedule.next_after(&next).unwrap().to_rfc3339(), "2024-03-10T03:15:00-04:00");
+    }
+
+    #[test]
+    fn test_schedule_invalid() {
+        assert!(Schedule::parse("* * * *").is_err());
+        assert!(Schedule::parse("60 * * * *").is_err());
+        assert!(Schedule::parse("*/0 * * * *").is_err());
+        assert!(Schedule::parse("5-1 * * * *").is_err());
+        assert!(Schedule::parse("* * * foo *").is_err());
+    }
+}
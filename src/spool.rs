@@ -0,0 +1,244 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::ezcron::{self, Delivery};
+use crate::logger::Logger;
+use crate::report::Report;
+
+pub const DEFAULT_MAX_AGE: u64 = 86400;
+pub const DEFAULT_RETRY_INTERVAL: u64 = 60;
+const MAX_RETRY_INTERVAL: u64 = 3600;
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SpoolEntry {
+    pub delivery: Delivery,
+    pub report: Report,
+    #[serde(default)]
+    pub log_tail: Vec<String>,
+    pub attempts: u32,
+    pub spooled_at: DateTime<Local>,
+    pub next_attempt_at: DateTime<Local>,
+    pub last_error: Option<String>,
+}
+
+impl SpoolEntry {
+    pub fn new(delivery: Delivery, report: Report, log_tail: Vec<String>, error: Option<String>, now: DateTime<Local>, retry_interval: u64) -> Self {
+        Self {
+            delivery,
+            report,
+            log_tail,
+            attempts: 1,
+            spooled_at: now,
+            next_attempt_at: now + Duration::seconds(retry_interval as i64),
+            last_error: error,
+        }
+    }
+    pub fn is_due(&self, now: DateTime<Local>) -> bool {
+        self.next_attempt_at <= now
+    }
+    pub fn is_expired(&self, now: DateTime<Local>, max_age: u64) -> bool {
+        now - self.spooled_at >= Duration::seconds(max_age as i64)
+    }
+    pub fn retry_later(&mut self, error: Option<String>, now: DateTime<Local>, retry_interval: u64) {
+        // 再送の間隔は失敗するごとに倍にする
+        let interval = retry_interval
+            .saturating_mul(1 << self.attempts.min(16))
+            .min(MAX_RETRY_INTERVAL.max(retry_interval));
+        self.attempts += 1;
+        self.next_attempt_at = now + Duration::seconds(interval as i64);
+        self.last_error = error;
+    }
+}
+
+pub fn push(spool_dir: &str, entry: &SpoolEntry) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    fs::create_dir_all(spool_dir)?;
+    let path = Path::new(spool_dir)
+        .join(format!("{}-{}.json", entry.spooled_at.format("%Y%m%d-%H%M%S"), Uuid::new_v4()));
+    save(&path, entry)?;
+    Ok(path)
+}
+
+pub fn save(path: &Path, entry: &SpoolEntry) -> Result<(), Box<dyn std::error::Error>> {
+    // 一時ファイルに書き込んでから置き換える
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, serde_json::to_string(entry)?)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+pub fn load(path: &Path) -> Result<SpoolEntry, Box<dyn std::error::Error>> {
+    let json = fs::read_to_string(path)?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+pub fn list(spool_dir: &str) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    // スプールディレクトリが無ければ空とする
+    if !Path::new(spool_dir).is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut paths = fs::read_dir(spool_dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .collect::<Vec<PathBuf>>();
+    paths.sort();
+    Ok(paths)
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Flushed {
+    pub delivered: usize,
+    pub retrying: usize,
+    pub gave_up: usize,
+    pub broken: usize,
+}
+
+pub fn flush(conf: &Config) -> Result<Flushed, Box<dyn std::error::Error>> {
+    let mut flushed = Flushed::default();
+    let Some(spool_dir) = &conf.ezcron.spool_dir else { return Ok(flushed); };
+    let max_age = conf.ezcron.spool_max_age.unwrap_or(DEFAULT_MAX_AGE);
+    let retry_interval = conf.ezcron.spool_retry_interval.unwrap_or(DEFAULT_RETRY_INTERVAL);
+
+    for path in list(spool_dir)? {
+        let now = Local::now();
+        let mut entry = match load(&path) {
+            Ok(entry) => entry,
+            // 他の再送で削除されたものは飛ばす
+            Err(_) if !path.is_file() => continue,
+            Err(err) => {
+                // 読み込めないものは別名にして残りの再送を続ける
+                let broken_path = path.with_extension("json.broken");
+                let mut logger = Logger::new("flush-reports", &conf.ezcron.log_dir)?;
+                logger.write(&format!("spooled report loading error! '{}' '{}'", path.display(), err))?;
+                fs::rename(&path, &broken_path)?;
+                logger.write(&format!("spooled report moved to '{}'", broken_path.display()))?;
+                flushed.broken += 1;
+                continue;
+            },
+        };
+        if !entry.is_expired(now, max_age) && !entry.is_due(now) {
+            flushed.retrying += 1;
+            continue;
+        }
+
+        // 元のジョブのログファイルに追記する
+        let mut logger = match Logger::open(&entry.report.log) {
+            Ok(logger) => logger,
+            Err(_) => Logger::new("flush-reports", &conf.ezcron.log_dir)?,
+        };
+
+        // 期限を過ぎたものは諦める
+        if entry.is_expired(now, max_age) {
+            logger.write("--------")?;
+            logger.write(&format!("spooled report gave up after {} attempts! '{}'", entry.attempts, entry.last_error.as_deref().unwrap_or("")))?;
+            fs::remove_file(&path)?;
+            flushed.gave_up += 1;
+            continue;
+        }
+
+        // ジョブの環境変数を設定する
+        let options = conf.option.iter()
+            .chain(conf.options.get(&entry.report.identifer));
+        for option in options {
+            for (name, value) in option.env.iter() {
+                crate::env::set_var(name, value);
+            }
+        }
+
+        // 再送する
+        logger.write("--------")?;
+        logger.write(&format!("retrying spooled report!({}) '{}'", entry.attempts, path.display()))?;
+        let result = ezcron::deliver(&entry.delivery, conf.ezcron.smtp.as_ref(), &entry.report, &entry.log_tail, &mut logger)?;
+        if result.success {
+            fs::remove_file(&path)?;
+            flushed.delivered += 1;
+        } else {
+            let error = result.error
+                .or(result.exitcode.map(|code| format!("exit code({})", code)));
+            entry.retry_later(error, now, retry_interval);
+            logger.write(&format!("spooled report will be retried at {}", entry.next_attempt_at.to_rfc3339()))?;
+            save(&path, &entry)?;
+            flushed.retrying += 1;
+        }
+    }
+    Ok(flushed)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::path::Path;
+    use chrono::{Duration, Local};
+    use crate::config::{Config, ConfigEzCron, ConfigReporter};
+    use crate::ezcron::Delivery;
+    use crate::report::Report;
+    use crate::spool::{self, SpoolEntry};
+
+    #[test]
+    fn test_spool_entry() {
+        let now = Local::now();
+        let delivery = Delivery::Script { reporter: ConfigReporter::new("report.sh"), timeout: 10 };
+        let mut entry = SpoolEntry::new(delivery, Report::default(), Vec::new(), None, now, 60);
+        assert!(!entry.is_due(now));
+        assert!(entry.is_due(now + Duration::seconds(60)));
+        entry.retry_later(Some("error".to_string()), now, 60);
+        assert_eq!(entry.attempts, 2);
+        assert_eq!(entry.next_attempt_at, now + Duration::seconds(120));
+        entry.retry_later(None, now, 60);
+        assert_eq!(entry.next_attempt_at, now + Duration::seconds(240));
+        entry.attempts = 10;
+        entry.retry_later(None, now, 60);
+        assert_eq!(entry.next_attempt_at, now + Duration::seconds(3600));
+        assert!(!entry.is_expired(now + Duration::seconds(3599), 3600));
+        assert!(entry.is_expired(now + Duration::seconds(3600), 3600));
+    }
+
+    #[test]
+    fn test_spool_push() {
+        const SPOOL_DIR: &str = "test_spool_push";
+        let now = Local::now();
+        let delivery = Delivery::Script { reporter: ConfigReporter::new("report.sh"), timeout: 10 };
+        let report = Report {
+            identifer: "test".to_string(),
+            ..Default::default()
+        };
+        let entry = SpoolEntry::new(delivery, report, vec!["aaa".to_string()], None, now, 60);
+        let path = spool::push(SPOOL_DIR, &entry).unwrap();
+        let paths = spool::list(SPOOL_DIR).unwrap();
+        let loaded = spool::load(&path);
+        std::fs::remove_dir_all(SPOOL_DIR).unwrap();
+        assert_eq!(paths, vec![path]);
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.report.identifer, "test");
+        assert_eq!(loaded.report.uuid, entry.report.uuid);
+        assert_eq!(loaded.log_tail, vec!["aaa"]);
+        assert!(matches!(loaded.delivery, Delivery::Script { timeout: 10, .. }));
+    }
+
+    #[test]
+    fn test_spool_flush_broken() {
+        const SPOOL_DIR: &str = "test_spool_flush_broken";
+        std::fs::create_dir_all(SPOOL_DIR).unwrap();
+        std::fs::write(Path::new(SPOOL_DIR).join("20231211-005418-broken.json"), "{").unwrap();
+        let conf = Config {
+            ezcron: ConfigEzCron {
+                log_dir: SPOOL_DIR.to_string(),
+                pid_dir: SPOOL_DIR.to_string(),
+                spool_dir: Some(SPOOL_DIR.to_string()),
+                ..Default::default()
+            },
+            option: None,
+            options: HashMap::new(),
+        };
+        let flushed = spool::flush(&conf);
+        let paths = spool::list(SPOOL_DIR).unwrap();
+        let moved = Path::new(SPOOL_DIR).join("20231211-005418-broken.json.broken").is_file();
+        std::fs::remove_dir_all(SPOOL_DIR).unwrap();
+        assert_eq!(flushed.unwrap(), spool::Flushed { broken: 1, ..Default::default() });
+        assert!(paths.is_empty());
+        assert!(moved);
+    }
+}
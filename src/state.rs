@@ -0,0 +1,156 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, Duration, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::config::NotifyMode;
+use crate::report::{ReportOutcome, ReporterResult};
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct State {
+    pub last_outcome: Option<ReportOutcome>,
+    #[serde(default)]
+    pub consecutive_failures: u32,
+    #[serde(default)]
+    pub skipped: bool,
+    pub last_run_at: Option<DateTime<Local>>,
+    pub last_reported_at: Option<DateTime<Local>>,
+    #[serde(default)]
+    pub reported_at: Vec<DateTime<Local>>,
+    #[serde(default)]
+    pub suppressed_count: u32,
+    #[serde(default)]
+    pub last_reporters: Vec<ReporterResult>,
+}
+
+impl State {
+    pub fn path(identifer: &str, state_dir: &str) -> PathBuf {
+        Path::new(state_dir)
+            .join(format!("{}.json", identifer))
+    }
+    pub fn load(identifer: &str, state_dir: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        // 状態ファイルが無ければ初期値とする
+        let path = Self::path(identifer, state_dir);
+        if !path.is_file() {
+            return Ok(Self::default());
+        }
+        let json = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+    pub fn save(&self, identifer: &str, state_dir: &str) -> Result<(), Box<dyn std::error::Error>> {
+        // 一時ファイルに書き込んでから置き換える
+        fs::create_dir_all(state_dir)?;
+        let path = Self::path(identifer, state_dir);
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_string(&self)?)?;
+        fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+    pub fn update(&mut self, outcome: ReportOutcome, run_at: DateTime<Local>) {
+        if outcome.is_failure() {
+            self.consecutive_failures += 1;
+        } else {
+            self.consecutive_failures = 0;
+        }
+        self.last_outcome = Some(outcome);
+        self.last_run_at = Some(run_at);
+        self.skipped = false;
+    }
+    pub fn is_cooled_down(&self, now: DateTime<Local>, cooldown: u64) -> bool {
+        match self.last_reported_at {
+            Some(last_reported_at) => now - last_reported_at >= Duration::seconds(cooldown as i64),
+            None => true,
+        }
+    }
+    pub fn is_rate_limited(&mut self, now: DateTime<Local>, cooldown: Option<u64>, max_reports_per_hour: Option<u32>) -> bool {
+        // 1時間より前の通知の記録は削除する
+        self.reported_at.retain(|reported_at| now - *reported_at < Duration::hours(1));
+        if let Some(cooldown) = cooldown {
+            if !self.is_cooled_down(now, cooldown) {
+                return true;
+            }
+        }
+        if let Some(max_reports_per_hour) = max_reports_per_hour {
+            if self.reported_at.len() >= max_reports_per_hour as usize {
+                return true;
+            }
+        }
+        false
+    }
+    pub fn record_report(&mut self, now: DateTime<Local>) -> u32 {
+        // 通知を記録し、抑止した回数を返す
+        self.reported_at.retain(|reported_at| now - *reported_at < Duration::hours(1));
+        self.last_reported_at = Some(now);
+        self.reported_at.push(now);
+        std::mem::take(&mut self.suppressed_count)
+    }
+    pub fn record_suppressed(&mut self) {
+        self.suppressed_count += 1;
+    }
+}
+
+pub fn should_report(mode: NotifyMode, previous: Option<ReportOutcome>, current: ReportOutcome) -> bool {
+    match mode {
+        NotifyMode::Always => true,
+        NotifyMode::Change => previous != Some(current),
+        // 失敗に変わった時と、失敗から回復した時のみとする
+        NotifyMode::FailureAndRecovery => {
+            let was_failure = previous.is_some_and(|outcome| outcome.is_failure());
+            current.is_failure() != was_failure
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Local};
+    use crate::config::NotifyMode;
+    use crate::report::ReportOutcome;
+    use crate::state::{self, State};
+
+    #[test]
+    fn test_state_save_load() {
+        const STATE_DIR: &str = "test_state_save_load";
+        let mut state = State::load("test", STATE_DIR).unwrap();
+        assert_eq!(state.last_outcome, None);
+        state.update(ReportOutcome::Failure, Local::now());
+        state.update(ReportOutcome::Timeout, Local::now());
+        state.save("test", STATE_DIR).unwrap();
+        let state = State::load("test", STATE_DIR).unwrap();
+        std::fs::remove_dir_all(STATE_DIR).unwrap();
+        assert_eq!(state.last_outcome, Some(ReportOutcome::Timeout));
+        assert_eq!(state.consecutive_failures, 2);
+    }
+
+    #[test]
+    fn test_state_should_report() {
+        use ReportOutcome::*;
+        assert!(state::should_report(NotifyMode::Always, Some(Success), Success));
+        assert!(state::should_report(NotifyMode::Change, None, Success));
+        assert!(!state::should_report(NotifyMode::Change, Some(Failure), Failure));
+        assert!(state::should_report(NotifyMode::Change, Some(Warning), Success));
+        assert!(state::should_report(NotifyMode::FailureAndRecovery, None, Failure));
+        assert!(!state::should_report(NotifyMode::FailureAndRecovery, None, Success));
+        assert!(!state::should_report(NotifyMode::FailureAndRecovery, Some(Failure), Timeout));
+        assert!(state::should_report(NotifyMode::FailureAndRecovery, Some(Timeout), Warning));
+        assert!(!state::should_report(NotifyMode::FailureAndRecovery, Some(Success), Warning));
+    }
+
+    #[test]
+    fn test_state_rate_limit() {
+        let now = Local::now();
+        let mut state = State::default();
+        assert!(!state.is_rate_limited(now, Some(600), Some(2)));
+        assert_eq!(state.record_report(now - Duration::minutes(50)), 0);
+        assert!(!state.is_rate_limited(now, Some(600), Some(2)));
+        state.record_suppressed();
+        assert_eq!(state.record_report(now - Duration::minutes(5)), 1);
+        assert_eq!(state.suppressed_count, 0);
+        // 前回の通知から10分経過していない
+        assert!(state.is_rate_limited(now, Some(600), None));
+        // 1時間以内に2回通知している
+        assert!(state.is_rate_limited(now, None, Some(2)));
+        assert!(!state.is_rate_limited(now + Duration::minutes(11), None, Some(2)));
+        assert!(!state.is_rate_limited(now, None, None));
+    }
+}
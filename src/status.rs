@@ -0,0 +1,171 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use crate::pid;
+use crate::posix;
+
+#[derive(Debug, Serialize)]
+pub struct JobStatus {
+    pub identifer: String,
+    pub uuid: Option<String>,
+    pub pid: u32,
+    pub alive: bool,
+    pub start_at: Option<DateTime<Local>>,
+    pub elapsed: Option<f64>,
+    pub command: String,
+    pub log: Option<String>,
+}
+
+pub fn is_alive(pid: u32) -> bool {
+    // シグナル0を送って存在を確認する(権限が無い場合も存在する)
+    pid > 0 && match posix::kill(pid, 0) {
+        Ok(()) => true,
+        Err(err) => err.raw_os_error() == Some(libc::EPERM),
+    }
+}
+
+pub fn is_group_alive(pgid: u32) -> bool {
+    // プロセスグループのいずれかのプロセスが存在するか確認する
+    pgid > 0 && match posix::kill_group(pgid, 0) {
+        Ok(()) => true,
+        Err(err) => err.raw_os_error() == Some(libc::EPERM),
+    }
+}
+
+fn cmdline(pid: u32) -> String {
+    // 実行情報が無い場合は/procからコマンドラインを得る
+    fs::read(format!("/proc/{}/cmdline", pid))
+        .map(|cmdline| cmdline.split(|c| *c == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| String::from_utf8_lossy(arg).into_owned())
+            .collect::<Vec<String>>()
+            .join(" "))
+        .unwrap_or_default()
+}
+
+pub fn list(pid_dir: &str, now: DateTime<Local>) -> Result<Vec<JobStatus>, Box<dyn std::error::Error>> {
+    let mut statuses = Vec::new();
+    if !Path::new(pid_dir).is_dir() {
+        return Ok(statuses);
+    }
+    let mut paths = fs::read_dir(pid_dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    // 実行中の情報を読み込む
+    for path in paths.iter().filter(|path| path.extension().is_some_and(|ext| ext == "json")) {
+        let Ok(info) = pid::load_info(path) else { continue; };
+        statuses.push(JobStatus {
+            alive: is_alive(info.pid),
+            elapsed: Some((now - info.start_at).num_milliseconds() as f64 / 1000.0),
+            start_at: Some(info.start_at),
+            identifer: info.identifer,
+            uuid: Some(info.uuid),
+            pid: info.pid,
+            command: info.command,
+            log: Some(info.log),
+        });
+    }
+
+    // 実行中の情報が無いpidファイルを加える
+    for path in paths.iter().filter(|path| path.extension().is_some_and(|ext| ext == "pid")) {
+        let Some(identifer) = path.file_stem().map(|stem| stem.to_string_lossy().into_owned()) else { continue; };
+        // 読み込む間に終了して削除されたものは飛ばす
+        let pid: u32 = match fs::read_to_string(path) {
+            Ok(pid) => pid.trim().parse().unwrap_or(0),
+            Err(err) if err.kind() == ErrorKind::NotFound => continue,
+            Err(err) => return Err(err.into()),
+        };
+        if statuses.iter().any(|status| status.identifer == identifer && status.pid == pid) {
+            continue;
+        }
+        let start_at = match fs::metadata(path) {
+            Ok(metadata) => metadata.modified().ok().map(DateTime::<Local>::from),
+            Err(err) if err.kind() == ErrorKind::NotFound => continue,
+            Err(err) => return Err(err.into()),
+        };
+        statuses.push(JobStatus {
+            identifer,
+            uuid: None,
+            pid,
+            alive: is_alive(pid),
+            start_at,
+            elapsed: start_at.map(|start_at| (now - start_at).num_milliseconds() as f64 / 1000.0),
+            command: cmdline(pid),
+            log: None,
+        });
+    }
+    statuses.sort_by(|a, b| (&a.identifer, a.start_at).cmp(&(&b.identifer, b.start_at)));
+    Ok(statuses)
+}
+
+fn format_elapsed(elapsed: Option<f64>) -> String {
+    let Some(elapsed) = elapsed else { return "-".to_string(); };
+    let seconds = elapsed as u64;
+    format!("{:02}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+pub fn format(statuses: &[JobStatus]) -> String {
+    let mut text = format!("{:<16} {:>8} {:<7} {:<19} {:>8} {}\n", "IDENTIFER", "PID", "STATE", "START", "ELAPSED", "COMMAND");
+    for status in statuses {
+        text.push_str(&format!("{:<16} {:>8} {:<7} {:<19} {:>8} {}\n",
+            status.identifer,
+            status.pid,
+            if status.alive { "running" } else { "stale" },
+            status.start_at.map_or("-".to_string(), |start_at| start_at.format("%Y-%m-%d %H:%M:%S").to_string()),
+            format_elapsed(status.elapsed),
+            status.command));
+        if let Some(log) = &status.log {
+            text.push_str(&format!("{:<16} log: {}\n", "", log));
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Local};
+    use crate::pid::{Pid, RunInfo};
+    use crate::status;
+
+    #[test]
+    fn test_status_list() {
+        const PID_DIR: &str = "test_status_list";
+        std::fs::create_dir_all(PID_DIR).unwrap();
+        let now = Local::now();
+        let pid = std::process::id();
+        let mut pid_file = Pid::new("job01", false, PID_DIR);
+        pid_file.touch(pid).unwrap();
+        pid_file.write_info(&RunInfo {
+            identifer: "job01".to_string(),
+            uuid: "uuid01".to_string(),
+            pid,
+            start_at: now - Duration::seconds(90),
+            command: "sleep 100".to_string(),
+            log: "job01.log".to_string(),
+        }).unwrap();
+        // 実行情報が無く、終了しているpidファイル
+        std::fs::write("test_status_list/job02.pid", "999999999").unwrap();
+        // 読み込む間に削除されたpidファイル
+        std::os::unix::fs::symlink("job03.missing", "test_status_list/job03.pid").unwrap();
+        let statuses = status::list(PID_DIR, now);
+        drop(pid_file);
+        let remains = status::list(PID_DIR, now);
+        std::fs::remove_dir_all(PID_DIR).unwrap();
+        let statuses = statuses.unwrap();
+        assert_eq!(statuses.len(), 2);
+        assert_eq!(statuses[0].identifer, "job01");
+        assert_eq!(statuses[0].uuid, Some("uuid01".to_string()));
+        assert!(statuses[0].alive);
+        assert_eq!(statuses[0].elapsed, Some(90.0));
+        assert_eq!(statuses[0].log, Some("job01.log".to_string()));
+        assert_eq!(statuses[1].identifer, "job02");
+        assert!(!statuses[1].alive);
+        assert!(status::format(&statuses).contains("00:01:30 sleep 100\n"));
+        assert_eq!(remains.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,120 @@
+use std::fs;
+use chrono::DateTime;
+use minijinja::{context, Environment, Error, ErrorKind, Value};
+
+use crate::report::Report;
+
+pub const LOG_TAIL_LINES: usize = 50;
+
+fn date(value: String, format: Option<String>) -> Result<String, Error> {
+    // RFC3339の日時を指定した書式に変換する
+    let datetime = DateTime::parse_from_rfc3339(&value)
+        .map_err(|err| Error::new(ErrorKind::InvalidOperation, format!("invalid datetime '{}': {}", value, err)))?;
+    let format = format.unwrap_or("%Y-%m-%d %H:%M:%S".to_string());
+    Ok(datetime.format(&format).to_string())
+}
+
+fn duration(seconds: Option<f64>) -> String {
+    // 秒数を"1h2m3s"の形式に変換する(開始の通知など、終了していなければ空とする)
+    let Some(seconds) = seconds else { return String::new(); };
+    let seconds = seconds as u64;
+    let (hours, minutes, seconds) = (seconds / 3600, seconds / 60 % 60, seconds % 60);
+    if hours > 0 {
+        format!("{}h{}m{}s", hours, minutes, seconds)
+    } else if minutes > 0 {
+        format!("{}m{}s", minutes, seconds)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+pub fn source(template: &Option<String>, template_file: &Option<String>) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    if let Some(template) = template {
+        return Ok(Some(template.clone()));
+    }
+    match template_file {
+        Some(template_file) => Ok(Some(fs::read_to_string(template_file)?)),
+        None => Ok(None),
+    }
+}
+
+pub fn render(source: &str, report: &Report, log_tail: &[String]) -> Result<String, Box<dyn std::error::Error>> {
+    let mut env = Environment::new();
+    env.add_filter("date", date);
+    env.add_filter("duration", duration);
+    env.add_template("report", source)?;
+
+    // レポートの各項目に、ログの末尾、所要時間、JSONを加える
+    let duration = report.end_at
+        .map(|end_at| (end_at - report.start_at).num_milliseconds() as f64 / 1000.0);
+    let ctx = context! {
+        log_tail => log_tail.iter()
+            .map(|line| line.split_once('|').map_or(line.as_str(), |(_, text)| text))
+            .collect::<Vec<&str>>(),
+        duration => duration,
+        json => serde_json::to_string(report)?,
+        ..Value::from_serialize(report)
+    };
+    Ok(env.get_template("report")?.render(ctx)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Local, TimeZone};
+    use crate::report::{Report, ReportOutcome};
+    use crate::template;
+
+    #[test]
+    fn test_template_render() {
+        let start_at = Local.with_ymd_and_hms(2023, 12, 11, 0, 54, 18).unwrap();
+        let report = Report {
+            identifer: "test".to_string(),
+            exitcode: 1,
+            outcome: Some(ReportOutcome::Failure),
+            start_at,
+            end_at: Some(start_at + Duration::seconds(3723)),
+            ..Default::default()
+        };
+        let log_tail = vec!["2023-12-11T00:54:18|aaa".to_string(), "2023-12-11T00:54:19|bbb".to_string()];
+        let source = "{{ identifer }} {{ outcome }}({{ exitcode }}) {{ start_at | date('%H:%M') }} {{ duration | duration }}\n{{ log_tail | join('\n') }}";
+        let rendered = template::render(source, &report, &log_tail).unwrap();
+        assert_eq!(rendered, "test failure(1) 00:54 1h2m3s\naaa\nbbb");
+    }
+
+    #[test]
+    // 開始の通知のように終了していない場合も変換できるか確認する
+    fn test_template_render_notify() {
+        let report = Report {
+            identifer: "test".to_string(),
+            ..Default::default()
+        };
+        let source = "{{ identifer }} started[{{ duration | duration }}][{{ undefined_value | duration }}]";
+        let rendered = template::render(source, &report, &[]).unwrap();
+        assert_eq!(rendered, "test started[][]");
+    }
+
+    #[test]
+    fn test_template_render_json() {
+        let report = Report {
+            identifer: "test".to_string(),
+            ..Default::default()
+        };
+        let rendered = template::render("{{ json }}", &report, &[]).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+        assert_eq!(json["identifer"], "test");
+    }
+
+    #[test]
+    fn test_template_render_error() {
+        let report = Report::default();
+        assert!(template::render("{{ identifer ", &report, &[]).is_err());
+        assert!(template::render("{{ identifer | date }}", &report, &[]).is_err());
+    }
+
+    #[test]
+    fn test_template_source() {
+        assert_eq!(template::source(&Some("aaa".to_string()), &None).unwrap(), Some("aaa".to_string()));
+        assert_eq!(template::source(&None, &None).unwrap(), None);
+        assert!(template::source(&None, &Some("not_found.tmpl".to_string())).is_err());
+    }
+}
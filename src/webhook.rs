@@ -0,0 +1,172 @@
+use std::thread;
+use std::time::Duration;
+use ureq::AgentBuilder;
+
+use crate::config::ConfigWebhook;
+use crate::logger::Logger;
+
+const DEFAULT_TIMEOUT: u64 = 10;
+const DEFAULT_RETRIES: u32 = 2;
+const DEFAULT_RETRY_INTERVAL: u64 = 1;
+// 実行の終了を待たせるため再送の間隔は長くしない
+const MAX_RETRY_INTERVAL: u64 = 60;
+
+fn retry_wait(retry_interval: u64, attempt: u32) -> u64 {
+    // 再送するごとに間隔を倍にする(指定した間隔より長い上限は超えない)
+    retry_interval
+        .saturating_mul(1u64.checked_shl(attempt.saturating_sub(1)).unwrap_or(u64::MAX))
+        .min(MAX_RETRY_INTERVAL.max(retry_interval))
+}
+
+pub fn send(webhook: &ConfigWebhook, body: &str, logger: &mut Logger) -> Result<(), Box<dyn std::error::Error>> {
+    // URL、ヘッダの環境変数を展開する
+    let url = crate::env::change_var(&webhook.url);
+    let headers: Vec<(String, String)> = webhook.headers.iter()
+        .map(|(name, value)| (name.clone(), crate::env::change_var(value)))
+        .collect();
+
+    let agent = AgentBuilder::new()
+        .timeout(Duration::from_secs(webhook.timeout.unwrap_or(DEFAULT_TIMEOUT)))
+        .build();
+    let retries = webhook.retries.unwrap_or(DEFAULT_RETRIES);
+    let retry_interval = webhook.retry_interval.unwrap_or(DEFAULT_RETRY_INTERVAL);
+
+    let mut attempt = 0;
+    loop {
+        // リクエストの作成
+        let mut request = agent.request(&webhook.method, &url)
+            .set("Content-Type", "application/json");
+        for (name, value) in headers.iter() {
+            request = request.set(name, value);
+        }
+
+        // リクエストの送信
+        let err = match request.send_string(body) {
+            Ok(response) => {
+                logger.write(&format!("webhook response status({})", response.status()))?;
+                return Ok(());
+            },
+            Err(ureq::Error::Status(code, response)) => {
+                logger.write(&format!("webhook response status({})", code))?;
+                // クライアントエラーは再送しない
+                if (400..500).contains(&code) && code != 408 && code != 429 {
+                    return Err(Box::new(ureq::Error::Status(code, response)));
+                }
+                ureq::Error::Status(code, response)
+            },
+            Err(err) => {
+                logger.write(&format!("webhook request error! '{}'", err))?;
+                err
+            },
+        };
+
+        // 再送回数を超えたら終了する
+        if attempt >= retries {
+            return Err(Box::new(err));
+        }
+        attempt += 1;
+        let wait = retry_wait(retry_interval, attempt);
+        logger.write(&format!("webhook retrying({}/{}) after {} seconds", attempt, retries, wait))?;
+        thread::sleep(Duration::from_secs(wait));
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread::{self, JoinHandle};
+    use crate::config::ConfigWebhook;
+    use crate::logger::Logger;
+    use crate::webhook;
+
+    pub struct TestRequest {
+        pub request_line: String,
+        pub headers: Vec<String>,
+        pub body: String,
+    }
+
+    // 指定したステータスを順に返すHTTPサーバを起動する
+    pub fn serve(statuses: Vec<u16>) -> (String, JoinHandle<Vec<TestRequest>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for status in statuses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut headers = Vec::new();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end().to_string();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap();
+                    }
+                    headers.push(line);
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                write!(stream, "HTTP/1.1 {} TEST\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status).unwrap();
+                requests.push(TestRequest {
+                    request_line: request_line.trim_end().to_string(),
+                    headers,
+                    body: String::from_utf8(body).unwrap(),
+                });
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn test_webhook_send() {
+        const LOG_DIR: &str = "test_webhook_send";
+        std::fs::create_dir_all(LOG_DIR).unwrap();
+        let mut logger = Logger::new("test", LOG_DIR).unwrap();
+        let (url, handle) = serve(vec![503, 200]);
+        std::env::set_var("TEST_WEBHOOK_TOKEN", "secret");
+        let mut webhook = ConfigWebhook::new(&url);
+        webhook.headers.insert("Authorization".to_string(), "Bearer ${TEST_WEBHOOK_TOKEN}".to_string());
+        webhook.retry_interval = Some(0);
+        let result = webhook::send(&webhook, r#"{"identifer":"test"}"#, &mut logger);
+        let requests = handle.join().unwrap();
+        std::fs::remove_dir_all(LOG_DIR).unwrap();
+        assert!(result.is_ok());
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].request_line, "POST /hook HTTP/1.1");
+        assert!(requests[1].headers.contains(&"Authorization: Bearer secret".to_string()));
+        assert_eq!(requests[1].body, r#"{"identifer":"test"}"#);
+    }
+
+    #[test]
+    fn test_webhook_retry_wait() {
+        assert_eq!(webhook::retry_wait(1, 1), 1);
+        assert_eq!(webhook::retry_wait(1, 3), 4);
+        assert_eq!(webhook::retry_wait(1, 20), 60);
+        assert_eq!(webhook::retry_wait(5, u32::MAX), 60);
+        assert_eq!(webhook::retry_wait(120, 5), 120);
+    }
+
+    #[test]
+    fn test_webhook_send_error() {
+        const LOG_DIR: &str = "test_webhook_send_error";
+        std::fs::create_dir_all(LOG_DIR).unwrap();
+        let mut logger = Logger::new("test", LOG_DIR).unwrap();
+        let (url, handle) = serve(vec![404]);
+        let mut webhook = ConfigWebhook::new(&url);
+        webhook.method = "PUT".to_string();
+        let result = webhook::send(&webhook, "{}", &mut logger);
+        let requests = handle.join().unwrap();
+        std::fs::remove_dir_all(LOG_DIR).unwrap();
+        assert!(result.is_err());
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].request_line, "PUT /hook HTTP/1.1");
+    }
+}